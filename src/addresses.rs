@@ -1,25 +1,36 @@
 //! The module declares structures to hold the address information and
 //! a set of functions to parse the raw data.
+use itertools::Itertools;
 use nom::branch::alt;
-use nom::bytes::complete::{tag, tag_no_case, take_until1};
-use nom::character::complete::{alpha0, digit1, multispace0};
-use nom::combinator::{map, map_res, opt, recognize, value};
+use nom::bytes::complete::{tag, tag_no_case, take_till, take_till1, take_until, take_while};
+use nom::character::complete::{char, digit1, multispace0, multispace1, satisfy};
+use nom::combinator::{
+    all_consuming, consumed, eof, map, map_res, not, opt, peek, recognize, value, verify,
+};
 use nom::error::Error;
-use nom::multi::{many1, separated_list0};
-use nom::sequence::{delimited, pair, separated_pair};
+use nom::multi::{many0, separated_list1};
+use nom::sequence::{delimited, pair, preceded, separated_pair, terminated, tuple};
 use nom::{Err, IResult};
+use std::borrow::Cow;
+use std::collections::BTreeSet;
+use std::fmt;
 
-#[derive(Eq, PartialEq, Clone, Debug)]
-pub(crate) struct BrojNumber<'a> {
+#[derive(Eq, PartialEq, Hash, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BrojNumber<'a> {
     value: usize,
-    extension: Option<&'a str>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(borrow, default, skip_serializing_if = "Option::is_none")
+    )]
+    extension: Option<Cow<'a, str>>,
 }
 
 impl<'a> From<(usize, Option<&'a str>)> for BrojNumber<'a> {
     fn from((v, e): (usize, Option<&'a str>)) -> Self {
         Self {
             value: v,
-            extension: e,
+            extension: e.map(Cow::Borrowed),
         }
     }
 }
@@ -33,43 +44,306 @@ impl<'a> From<usize> for BrojNumber<'a> {
     }
 }
 
-#[derive(Eq, PartialEq, Clone, Debug)]
-pub(crate) struct BrojRange<'a> {
+impl<'a> BrojNumber<'a> {
+    /// The number without the extension.
+    ///
+    /// ```
+    /// use beobot::addresses::BrojNumber;
+    ///
+    /// let number = BrojNumber::from((36, Some("A")));
+    /// assert_eq!(number.value(), 36);
+    /// assert_eq!(number.extension(), Some("A"));
+    /// ```
+    pub fn value(&self) -> usize {
+        self.value
+    }
+
+    /// The letters or the fraction after the number, as `A` in `36A`.
+    pub fn extension(&self) -> Option<&str> {
+        self.extension.as_deref()
+    }
+
+    /// Copies the borrowed extension, so the number no longer borrows the
+    /// parsed text.
+    pub fn into_owned(self) -> BrojNumber<'static> {
+        BrojNumber {
+            value: self.value,
+            extension: self.extension.map(into_owned_text),
+        }
+    }
+}
+
+/// Turns borrowed text into owned one, the owned text is moved as is.
+fn into_owned_text(text: Cow<'_, str>) -> Cow<'static, str> {
+    Cow::Owned(text.into_owned())
+}
+
+/// The side of the street a house number is on.
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Parity {
+    Even,
+    Odd,
+}
+
+impl Parity {
+    pub fn of(value: usize) -> Self {
+        if value.is_multiple_of(2) {
+            Parity::Even
+        } else {
+            Parity::Odd
+        }
+    }
+}
+
+/// Which sides of the street a record covers.
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Coverage {
+    EvenOnly,
+    OddOnly,
+    Both,
+    /// The record has no numbers to tell the side by, e.g. `BB` only.
+    Unknown,
+}
+
+/// A numbered part of a street, given after its name: `14 DEO` in
+/// `BATAJNIČKI DRUM 14 DEO` or `NOVA 8` in `KLISINA NOVA 8`.
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Part {
+    /// A section of the street, `<n> DEO`.
+    Deo(usize),
+    /// A new street not named yet, `NOVA <n>`.
+    Nova(usize),
+}
+
+impl fmt::Display for Part {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Part::Deo(n) => write!(f, "{n} DEO"),
+            Part::Nova(n) => write!(f, "NOVA {n}"),
+        }
+    }
+}
+
+#[derive(Eq, PartialEq, Hash, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BrojRange<'a> {
+    #[cfg_attr(feature = "serde", serde(borrow))]
     from: BrojNumber<'a>,
+    #[cfg_attr(feature = "serde", serde(borrow))]
     to: BrojNumber<'a>,
+    /// The side of the street given explicitly, as in `2-90 (parne)`.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    explicit_parity: Option<Parity>,
 }
 
 impl<'a> From<(usize, usize)> for BrojRange<'a> {
     fn from((from, to): (usize, usize)) -> Self {
-        Self {
-            from: BrojNumber::from(from),
-            to: BrojNumber::from(to),
-        }
+        Self::from((BrojNumber::from(from), BrojNumber::from(to)))
     }
 }
 
 impl<'a> From<((usize, Option<&'a str>), (usize, Option<&'a str>))> for BrojRange<'a> {
     fn from((from, to): ((usize, Option<&'a str>), (usize, Option<&'a str>))) -> Self {
-        Self {
-            from: BrojNumber::from(from),
-            to: BrojNumber::from(to),
-        }
+        Self::from((BrojNumber::from(from), BrojNumber::from(to)))
     }
 }
 
 impl<'a> From<(BrojNumber<'a>, BrojNumber<'a>)> for BrojRange<'a> {
     fn from((from, to): (BrojNumber<'a>, BrojNumber<'a>)) -> Self {
-        Self { from, to }
+        Self {
+            from,
+            to,
+            explicit_parity: None,
+        }
     }
 }
 
-#[derive(Eq, PartialEq, Clone, Debug)]
-pub(crate) enum Broj<'a> {
+impl<'a> BrojRange<'a> {
+    /// The first number of the range. The endpoints are not named `from`
+    /// and `to`, which would shadow `From::from` in `BrojRange::from(..)`.
+    ///
+    /// ```
+    /// use beobot::addresses::BrojRange;
+    ///
+    /// let range = BrojRange::from(((294, None), (296, Some("F"))));
+    /// assert_eq!(range.start().value(), 294);
+    /// assert_eq!(range.end().extension(), Some("F"));
+    /// ```
+    pub fn start(&self) -> &BrojNumber<'a> {
+        &self.from
+    }
+
+    /// The last number of the range.
+    pub fn end(&self) -> &BrojNumber<'a> {
+        &self.to
+    }
+
+    /// See [`BrojNumber::into_owned`].
+    pub fn into_owned(self) -> BrojRange<'static> {
+        BrojRange {
+            from: self.from.into_owned(),
+            to: self.to.into_owned(),
+            explicit_parity: self.explicit_parity,
+        }
+    }
+
+    /// Sets the side of the street explicitly, so only the numbers of that
+    /// side are in the range.
+    pub fn with_parity(mut self, parity: Parity) -> Self {
+        self.explicit_parity = Some(parity);
+        self
+    }
+
+    /// The side of the street of the range. Unless it is given explicitly
+    /// it is inferred from the endpoints: `2-90` is even, `1-89` is odd and
+    /// `1-10` spans both sides.
+    pub fn parity(&self) -> Option<Parity> {
+        if self.explicit_parity.is_some() {
+            return self.explicit_parity;
+        }
+        let parity = Parity::of(self.from.value);
+        (parity == Parity::of(self.to.value)).then_some(parity)
+    }
+
+    /// Tells whether the side of the street was given explicitly.
+    pub fn has_explicit_parity(&self) -> bool {
+        self.explicit_parity.is_some()
+    }
+
+    /// Yields every house number of the range. The endpoints keep their
+    /// extensions, the numbers in between have none, so `294-296F` yields
+    /// `294`, `295` and `296F`. A reversed range yields its endpoints only.
+    /// With an explicit parity the numbers in between are of that side only.
+    pub fn expand(&self) -> impl Iterator<Item = BrojNumber<'a>> + '_ {
+        let inner = if self.from.value < self.to.value {
+            self.from.value + 1..self.to.value
+        } else {
+            0..0
+        };
+        let last = (self.to != self.from).then(|| self.to.clone());
+
+        std::iter::once(self.from.clone())
+            .chain(
+                inner
+                    .filter(|value| {
+                        self.explicit_parity
+                            .is_none_or(|parity| Parity::of(*value) == parity)
+                    })
+                    .map(BrojNumber::from),
+            )
+            .chain(last)
+    }
+}
+
+/// An entry of the numbers of a street. With the `serde` feature `Bez` is
+/// written as `"bb"`, a letter as `{"letter": "A"}`, a number as
+/// `{"value": 12, "extension": "A"}`, a range as `{"from": .., "to": ..}`
+/// and an open range as `{"from": ..}`.
+#[derive(Eq, PartialEq, Hash, Clone, Debug)]
+pub enum Broj<'a> {
     Bez,
+    /// A building designated by a letter only, as `A`.
+    Letter(Cow<'a, str>),
     Number(BrojNumber<'a>),
     Range(BrojRange<'a>),
+    /// A range open on the upper end, as `120-`, the number and every
+    /// number after it.
+    OpenRange(BrojNumber<'a>),
+}
+
+/// The `"bb"` marker of a [`Broj::Bez`] entry.
+#[cfg(feature = "serde")]
+struct BezMarker;
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for BezMarker {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str("bb")
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for BezMarker {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = <Cow<'de, str>>::deserialize(deserializer)?;
+        if text.eq_ignore_ascii_case("bb") {
+            Ok(BezMarker)
+        } else {
+            Err(serde::de::Error::custom(format!(
+                "expected \"bb\", got \"{text}\""
+            )))
+        }
+    }
+}
+
+/// The shape [`Broj`] is written in, see there.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize)]
+#[serde(untagged)]
+enum BrojRef<'r, 'a> {
+    Bez(BezMarker),
+    Letter { letter: &'r str },
+    Range(&'r BrojRange<'a>),
+    OpenRange { from: &'r BrojNumber<'a> },
+    Number(&'r BrojNumber<'a>),
+}
+
+/// The shape [`Broj`] is read from. A range is tried before an open range,
+/// which has no `to`, and both before a number.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum BrojRepr<'a> {
+    Bez(BezMarker),
+    Letter {
+        #[serde(borrow)]
+        letter: Cow<'a, str>,
+    },
+    Range(#[serde(borrow)] BrojRange<'a>),
+    OpenRange {
+        #[serde(borrow)]
+        from: BrojNumber<'a>,
+    },
+    Number(#[serde(borrow)] BrojNumber<'a>),
 }
 
+#[cfg(feature = "serde")]
+impl<'a> serde::Serialize for Broj<'a> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let repr = match self {
+            Broj::Bez => BrojRef::Bez(BezMarker),
+            Broj::Letter(letter) => BrojRef::Letter { letter },
+            Broj::Number(number) => BrojRef::Number(number),
+            Broj::Range(range) => BrojRef::Range(range),
+            Broj::OpenRange(from) => BrojRef::OpenRange { from },
+        };
+        repr.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de: 'a, 'a> serde::Deserialize<'de> for Broj<'a> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(match BrojRepr::deserialize(deserializer)? {
+            BrojRepr::Bez(_) => Broj::Bez,
+            BrojRepr::Letter { letter } => Broj::Letter(letter),
+            BrojRepr::Number(number) => Broj::Number(number),
+            BrojRepr::Range(range) => Broj::Range(range),
+            BrojRepr::OpenRange { from } => Broj::OpenRange(from),
+        })
+    }
+}
+
+/// The number an open range is expanded up to, as no street has that many
+/// houses. [`AddressRecord::contains`] does not limit an open range.
+pub const OPEN_RANGE_CAP: usize = 1000;
+
 impl<'a> From<BrojNumber<'a>> for Broj<'a> {
     fn from(v: BrojNumber<'a>) -> Self {
         Broj::Number(v)
@@ -82,225 +356,2764 @@ impl<'a> From<BrojRange<'a>> for Broj<'a> {
     }
 }
 
-#[derive(Eq, PartialEq, Debug)]
-pub(crate) struct AddressRecord<'a> {
-    street: &'a str,
-    numbers: Vec<Broj<'a>>,
-}
+/// Parses a single entry, as `123A`, `2-10` or `BB`. The whole text has to
+/// be the entry.
+impl<'a> TryFrom<&'a str> for Broj<'a> {
+    type Error = ParseError<'a>;
 
-impl<'a> AddressRecord<'a> {
-    pub(crate) fn new(street: &'a str, numbers: Vec<Broj<'a>>) -> Self {
-        Self { street, numbers }
+    fn try_from(input: &'a str) -> Result<Self, Self::Error> {
+        all_consuming(broj)(input)
+            .map(|(_, broj)| broj)
+            .map_err(|_| ParseError { chunk: input })
     }
 }
 
-impl<'a> From<(&'a str, Vec<Broj<'a>>)> for AddressRecord<'a> {
-    fn from((street, numbers): (&'a str, Vec<Broj<'a>>)) -> Self {
-        Self { street, numbers }
+impl<'a> fmt::Display for BrojNumber<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.value)?;
+        if let Some(extension) = &self.extension {
+            f.write_str(extension)?;
+        }
+        Ok(())
     }
 }
 
-/// Parser a regular address number with optional extension letter.
-fn address_number(input: &str) -> IResult<&str, BrojNumber<'_>> {
-    let digit_parser = map_res(digit1, |s: &str| s.parse::<usize>());
-    let ext_parser = map(
-        recognize(pair(alpha0, opt(pair(tag("/"), digit1)))),
-        |x: &str| if !x.is_empty() { Some(x) } else { None },
-    );
-    map(pair(digit_parser, ext_parser), BrojNumber::from)(input)
+impl<'a> fmt::Display for BrojRange<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.from, self.to)?;
+        match self.explicit_parity {
+            Some(Parity::Even) => write!(f, " (parne)"),
+            Some(Parity::Odd) => write!(f, " (neparne)"),
+            None => Ok(()),
+        }
+    }
 }
 
-/// Parse a range of addresses
-fn address_number_range(input: &str) -> IResult<&str, BrojRange<'_>> {
-    let parser = separated_pair(address_number, tag("-"), address_number);
-    map(parser, BrojRange::from)(input)
+impl<'a> fmt::Display for Broj<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Broj::Bez => f.write_str("BB"),
+            Broj::Letter(letter) => f.write_str(letter),
+            Broj::Number(number) => number.fmt(f),
+            Broj::Range(range) => range.fmt(f),
+            Broj::OpenRange(from) => write!(f, "{from}-"),
+        }
+    }
 }
 
-/// Parses an address number, a range of addresses or a special BB case.
-fn broj(input: &str) -> IResult<&str, Broj<'_>> {
-    let bb_parser = value(Broj::Bez, tag_no_case("bb"));
-    let number_parser = map(address_number, Broj::from);
-    let range_parser = map(address_number_range, Broj::from);
+impl<'a> Broj<'a> {
+    /// See [`BrojNumber::into_owned`].
+    pub fn into_owned(self) -> Broj<'static> {
+        match self {
+            Broj::Bez => Broj::Bez,
+            Broj::Letter(letter) => Broj::Letter(into_owned_text(letter)),
+            Broj::Number(number) => Broj::Number(number.into_owned()),
+            Broj::Range(range) => Broj::Range(range.into_owned()),
+            Broj::OpenRange(from) => Broj::OpenRange(from.into_owned()),
+        }
+    }
 
-    alt((bb_parser, range_parser, number_parser))(input)
+    /// Yields the house numbers denoted by the entry, nothing for `Bez`
+    /// and a letter. An open range is expanded up to [`OPEN_RANGE_CAP`].
+    pub fn expand(&self) -> impl Iterator<Item = BrojNumber<'a>> + '_ {
+        let (number, range, open) = match self {
+            Broj::Bez | Broj::Letter(_) => (None, None, None),
+            Broj::Number(number) => (Some(number.clone()), None, None),
+            Broj::Range(range) => (None, Some(range.expand()), None),
+            Broj::OpenRange(from) => (
+                Some(from.clone()),
+                None,
+                Some(from.value + 1..=OPEN_RANGE_CAP),
+            ),
+        };
+        number
+            .into_iter()
+            .chain(range.into_iter().flatten())
+            .chain(open.into_iter().flatten().map(BrojNumber::from))
+    }
 }
 
-/// Recognizes a list of addresses, ranges of addresses or special BB cases.
-fn broj_list(input: &str) -> IResult<&str, Vec<Broj<'_>>> {
-    let parser = separated_list0(tag(","), broj);
-    delimited(
-        multispace0,
-        // potentially we can simply skip the second element of the pair (the trailing comma)
-        map(pair(parser, opt(tag(","))), |(x, _)| x),
-        multispace0,
-    )(input)
+#[derive(Eq, PartialEq, Hash, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AddressRecord<'a> {
+    #[cfg_attr(feature = "serde", serde(borrow))]
+    street: Cow<'a, str>,
+    #[cfg_attr(feature = "serde", serde(borrow))]
+    numbers: Vec<Broj<'a>>,
+    /// The note put in parentheses after the street name, as `deo` in
+    /// `NOVOSADSKA (deo)`.
+    #[cfg_attr(
+        feature = "serde",
+        serde(borrow, default, skip_serializing_if = "Option::is_none")
+    )]
+    note: Option<Cow<'a, str>>,
+    /// The settlement the street is listed under, as `BATAJNICA` after the
+    /// `NASELJE BATAJNICA:` header of the row.
+    #[cfg_attr(
+        feature = "serde",
+        serde(borrow, default, skip_serializing_if = "Option::is_none")
+    )]
+    settlement: Option<Cow<'a, str>>,
 }
 
-/// Recognizes a pair of an address and the list of addresses' numbers.
-fn address_number_pair(input: &str) -> IResult<&str, AddressRecord<'_>> {
-    let take_pp = take_until1(":");
-    map(separated_pair(take_pp, tag(":"), broj_list), |(a, b)| {
-        AddressRecord::new(a.trim(), b)
-    })(input)
-}
+impl<'a> AddressRecord<'a> {
+    /// Copies the borrowed street, note and numbers, so the record
+    /// outlives the parsed text and can be sent to another task.
+    pub fn into_owned(self) -> AddressRecord<'static> {
+        AddressRecord {
+            street: into_owned_text(self.street),
+            numbers: self.numbers.into_iter().map(Broj::into_owned).collect(),
+            note: self.note.map(into_owned_text),
+            settlement: self.settlement.map(into_owned_text),
+        }
+    }
 
-/// Parse addresses info (row).
-fn addresses(input: &str) -> IResult<&str, Vec<AddressRecord<'_>>> {
-    many1(address_number_pair)(input)
-}
+    /// Creates a record, collapsing runs of whitespace in the street name
+    /// into a single space. A note in parentheses ending the name is moved
+    /// out of it into [`AddressRecord::note`].
+    pub fn new(street: &'a str, numbers: Vec<Broj<'a>>) -> Self {
+        let (street, note) = split_note(street);
+        Self {
+            street: normalize_whitespace(street),
+            numbers,
+            note: note.map(normalize_whitespace),
+            settlement: None,
+        }
+    }
 
-#[derive(Eq, PartialEq, Debug)]
-#[repr(transparent)]
-pub(crate) struct Addresses<'a> {
-    items: Vec<AddressRecord<'a>>,
-}
+    /// Puts the record under the settlement, collapsing runs of whitespace
+    /// in its name.
+    pub fn with_settlement(mut self, settlement: &'a str) -> Self {
+        self.settlement = Some(normalize_whitespace(settlement));
+        self
+    }
 
-impl<'a> Addresses<'a> {
-    #[inline(always)]
-    pub(crate) fn parse(input: &'a str) -> Result<Addresses<'a>, Err<Error<&str>>> {
-        match addresses(input) {
-            Ok((_, items)) => Ok(Self { items }),
-            Err(err) => Err(err),
+    /// Creates a record out of the street name and the text of its numbers,
+    /// as `("MALA", "2-10,1")`. The whole text has to be a list of numbers.
+    pub fn from_raw(street: &'a str, numbers_raw: &'a str) -> Result<Self, ParseError<'a>> {
+        match all_consuming(broj_list)(numbers_raw) {
+            Ok((_, numbers)) => Ok(Self::new(street.trim(), numbers)),
+            Err(_) => Err(ParseError {
+                chunk: numbers_raw.trim(),
+            }),
         }
     }
-}
 
-impl<'a> IntoIterator for Addresses<'a> {
-    type Item = <Vec<AddressRecord<'a>> as IntoIterator>::Item;
-    type IntoIter = <Vec<AddressRecord<'a>> as IntoIterator>::IntoIter;
+    /// The street name.
+    pub fn street(&self) -> &str {
+        &self.street
+    }
 
-    fn into_iter(self) -> Self::IntoIter {
-        self.items.into_iter()
+    /// The street name without the numbered part, see [`AddressRecord::part`].
+    ///
+    /// ```
+    /// use beobot::addresses::{AddressRecord, Part};
+    ///
+    /// let record = AddressRecord::new("BATAJNIČKI DRUM 14 DEO", vec![]);
+    /// assert_eq!(record.base(), "BATAJNIČKI DRUM");
+    /// assert_eq!(record.part(), Some(Part::Deo(14)));
+    /// assert_eq!(record.street(), "BATAJNIČKI DRUM 14 DEO");
+    /// ```
+    pub fn base(&self) -> &str {
+        split_part(&self.street).0
     }
-}
 
-#[cfg(test)]
-mod tests {
+    /// The numbered part of the street, as `14 DEO` or `NOVA 8`, if the
+    /// name ends with one.
+    pub fn part(&self) -> Option<Part> {
+        split_part(&self.street).1
+    }
 
-    use super::*;
+    /// The note given in parentheses after the street name, if any.
+    pub fn note(&self) -> Option<&str> {
+        self.note.as_deref()
+    }
 
-    #[test]
-    fn test_can_parse_complicated_address() {
-        let res = address_number("36A/1").expect("parse the compilcated regular address");
-        assert_eq!(res, ("", BrojNumber::from((36, Some("A/1")))));
+    /// The settlement the street is listed under, if the row has a
+    /// `NASELJE <name>:` header before it.
+    ///
+    /// ```
+    /// use beobot::addresses::Addresses;
+    ///
+    /// let addresses = Addresses::parse("MALA: 1,  NASELJE ZEMUN:   BRODSKA: 2-18,").unwrap();
+    /// assert_eq!(addresses.get("MALA").unwrap().settlement(), None);
+    /// assert_eq!(addresses.get("BRODSKA").unwrap().settlement(), Some("ZEMUN"));
+    /// ```
+    pub fn settlement(&self) -> Option<&str> {
+        self.settlement.as_deref()
     }
 
-    #[test]
-    fn test_can_parse_a_range_of_addresses() {
-        let res = address_number_range("123-321").expect("parse the range of addresses");
-        assert_eq!(res, ("", BrojRange::from((123, 321))))
+    /// The house numbers of the street, as listed in the source.
+    ///
+    /// ```
+    /// use beobot::addresses::{AddressRecord, Broj};
+    ///
+    /// let record = AddressRecord::from_raw("MALA", "BB,2-10,36A").unwrap();
+    /// assert_eq!(record.street(), "MALA");
+    /// assert_eq!(record.numbers().len(), 3);
+    /// assert_eq!(record.numbers()[0], Broj::Bez);
+    /// ```
+    pub fn numbers(&self) -> &[Broj<'a>] {
+        &self.numbers
     }
 
-    #[test]
-    fn test_can_parse_one_of() {
-        let res = broj("BB").expect("can recognize BB");
-        assert_eq!(res, ("", Broj::Bez));
+    /// Tells whether the whole street is affected, that is the street is
+    /// listed without numbers.
+    ///
+    /// ```
+    /// use beobot::addresses::Addresses;
+    ///
+    /// let addresses = Addresses::parse("MALA: 1,  BRODSKA,  PILOTSKA: 2-18,").unwrap();
+    /// let whole = addresses
+    ///     .into_iter()
+    ///     .filter(|record| record.is_whole_street())
+    ///     .map(|record| record.street().to_owned())
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(whole, ["BRODSKA"]);
+    /// ```
+    pub fn is_whole_street(&self) -> bool {
+        self.numbers.is_empty()
+    }
 
-        let res = broj("123A").expect("can recognize an address number");
-        assert_eq!(res, ("", Broj::from(BrojNumber::from((123, Some("A"))))));
+    /// Tells which sides of the street the record covers. A range spanning
+    /// both sides covers both, `Bez` entries and letters are not taken into
+    /// account.
+    pub fn coverage(&self) -> Coverage {
+        let (mut even, mut odd) = (false, false);
+        for broj in &self.numbers {
+            let parity = match broj {
+                Broj::Bez | Broj::Letter(_) => continue,
+                Broj::Number(n) => Some(Parity::of(n.value)),
+                Broj::Range(r) => r.parity(),
+                Broj::OpenRange(_) => None,
+            };
+            match parity {
+                Some(Parity::Even) => even = true,
+                Some(Parity::Odd) => odd = true,
+                None => (even, odd) = (true, true),
+            }
+        }
 
-        let res = broj("123A-321B").expect("can recognize an addresses range");
-        assert_eq!(
-            res,
-            (
-                "",
-                Broj::from(BrojRange::from(((123, Some("A")), (321, Some("B")))))
-            )
-        );
+        match (even, odd) {
+            (true, true) => Coverage::Both,
+            (true, false) => Coverage::EvenOnly,
+            (false, true) => Coverage::OddOnly,
+            (false, false) => Coverage::Unknown,
+        }
     }
 
-    #[test]
-    fn test_can_parse_numbers_sequences() {
-        let res = broj_list("BB,123,123-321").expect("parse the sequence of numbers");
-        assert_eq!(
-            res,
-            (
-                "",
-                vec![
-                    Broj::Bez,
-                    Broj::Number(BrojNumber::from(123)),
-                    Broj::Range(BrojRange::from((123, 321))),
-                ]
-            )
-        )
+    /// Tells whether the record lists a building without a number.
+    pub fn has_bez(&self) -> bool {
+        self.numbers.contains(&Broj::Bez)
     }
 
-    #[test]
-    fn test_ignores_trailing_whitespaces() {
-        let res =
-            broj_list("   BB,BB   ").expect("rejects whitespaces before and after the sequence");
-        assert_eq!(res, ("", vec![Broj::Bez, Broj::Bez,]))
+    /// The numbers in a canonical form for comparison: every number once,
+    /// in order, compressed back into ranges. A run of consecutive numbers
+    /// becomes a range, and a run of the numbers of one side, as `1,3,5`,
+    /// a range of that side, `1-5 (neparne)`. The numbers with an extension
+    /// stay single, `Bez` comes first and the letters follow it. The lowest
+    /// open range is kept as is and takes in the numbers after it.
+    pub fn canonical(&self) -> Vec<Broj<'a>> {
+        let open = self
+            .numbers
+            .iter()
+            .filter_map(|broj| match broj {
+                Broj::OpenRange(from) => Some(from),
+                _ => None,
+            })
+            .min_by_key(|from| from.value);
+
+        let mut letters = self
+            .numbers
+            .iter()
+            .filter_map(|broj| match broj {
+                Broj::Letter(letter) => Some(letter.clone()),
+                _ => None,
+            })
+            .collect_vec();
+        letters.sort_unstable();
+        letters.dedup();
+
+        let mut numbers = self
+            .numbers
+            .iter()
+            .filter(|broj| !matches!(broj, Broj::OpenRange(_)))
+            .flat_map(Broj::expand)
+            .filter(|number| open.is_none_or(|from| number.value < from.value))
+            .collect_vec();
+        numbers.sort_by(|a, b| {
+            (a.value, a.extension.as_deref()).cmp(&(b.value, b.extension.as_deref()))
+        });
+        numbers.dedup();
+
+        let (plain, extended): (Vec<_>, Vec<_>) = numbers
+            .into_iter()
+            .partition(|number| number.extension.is_none());
+
+        // consecutive numbers first, what is left single is joined by side
+        let mut ranges = Vec::new();
+        let mut singles = Vec::new();
+        for run in runs(plain.iter().map(|number| number.value), 1) {
+            match run {
+                (from, to) if from < to => ranges.push(Broj::from(BrojRange::from((from, to)))),
+                (value, _) => singles.push(value),
+            }
+        }
+        for parity in [Parity::Even, Parity::Odd] {
+            let side = singles.iter().copied().filter(|v| Parity::of(*v) == parity);
+            for run in runs(side, 2) {
+                match run {
+                    (from, to) if from < to => {
+                        ranges.push(Broj::from(BrojRange::from((from, to)).with_parity(parity)))
+                    }
+                    (value, _) => ranges.push(Broj::from(BrojNumber::from(value))),
+                }
+            }
+        }
+        ranges.extend(extended.into_iter().map(Broj::from));
+
+        ranges.sort_by(|a, b| broj_order(a).cmp(&broj_order(b)));
+        ranges.extend(open.cloned().map(Broj::OpenRange));
+
+        let bez = self.has_bez().then_some(Broj::Bez);
+        bez.into_iter()
+            .chain(letters.into_iter().map(Broj::Letter))
+            .chain(ranges)
+            .collect()
     }
 
-    #[test]
-    fn test_can_recognize_trailing_comma() {
-        let res = broj_list("BB,")
-            .expect("parse the sequence of addresses followed by the trailing comma");
-        assert_eq!(res, ("", vec![Broj::Bez,]));
+    /// Tells whether the record lists the house number. A single number has
+    /// to match the extension as well, ignoring its case, a range matches
+    /// every number between its endpoints whatever the extension, an open
+    /// range every number from its start on. `Bez` entries and letters never
+    /// match.
+    pub fn contains(&self, number: usize, extension: Option<&str>) -> bool {
+        let extension = extension.map(str::to_uppercase);
+        self.numbers.iter().any(|broj| match broj {
+            Broj::Bez | Broj::Letter(_) => false,
+            Broj::OpenRange(from) => from.value <= number,
+            Broj::Number(n) => {
+                n.value == number && n.extension.as_deref().map(str::to_uppercase) == extension
+            }
+            Broj::Range(r) => {
+                r.from.value <= number
+                    && number <= r.to.value
+                    && r.explicit_parity
+                        .is_none_or(|parity| Parity::of(number) == parity)
+            }
+        })
     }
 
-    #[test]
-    fn test_reject_simple_comma() {
-        let res = broj_list("   ,   ");
-        assert!(res.is_err());
+    /// Collapses runs of adjacent `Bez` entries into a single one.
+    /// The numbers and non-adjacent `Bez` entries are kept as they are.
+    pub fn dedup_bez(&mut self) {
+        self.numbers
+            .dedup_by(|a, b| *a == Broj::Bez && *b == Broj::Bez);
     }
 
-    #[test]
-    fn test_parse() {
-        let res = address_number_pair("  AUTOPUT ZA NOVI SAD  : BB,284,294-296F,").unwrap();
-        assert_eq!(
-            res,
-            (
-                "",
-                AddressRecord::new(
-                    "AUTOPUT ZA NOVI SAD",
-                    vec![
-                        Broj::Bez,
-                        Broj::Number(BrojNumber::from(284)),
-                        Broj::Range(BrojRange::from(((294, None), (296, Some("F"))))),
-                    ]
-                )
-            )
-        );
+    /// Checks the record against the default validation rules.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        self.validate_with(&ValidationRules::default())
     }
 
-    #[test]
-    fn test_parse_all_addresses() {
-        let res = addresses("AUTOPUT ZA NOVI SAD: BB,284,294-296F,  BATAJNIČKI DRUM: BB,261-265,269,283-293,299,303-303A,").expect("parse the address row");
+    /// Checks that the record does not contain house numbers which are
+    /// most likely data-entry noise: zeroes and values above `rules.max_number`.
+    /// With `rules.max_span` set, a range spanning more numbers than that is
+    /// suspicious as well, as `1-9999` is likely made of glued tokens.
+    pub fn validate_with(&self, rules: &ValidationRules) -> Result<(), ValidationError> {
+        let check = |number: &BrojNumber<'_>| {
+            if number.value == 0 {
+                Err(ValidationError::ZeroNumber {
+                    street: self.street.to_string(),
+                })
+            } else if number.value > rules.max_number {
+                Err(ValidationError::NumberTooLarge {
+                    street: self.street.to_string(),
+                    value: number.value,
+                    max: rules.max_number,
+                })
+            } else {
+                Ok(())
+            }
+        };
 
-        assert_eq!(
-            res,
-            (
-                "",
-                vec![
-                    AddressRecord::new(
-                        "AUTOPUT ZA NOVI SAD",
-                        vec![
-                            Broj::Bez,
-                            Broj::from(BrojNumber::from(284)),
-                            Broj::from(BrojRange::from(((294, None), (296, Some("F"))))),
-                        ]
-                    ),
-                    AddressRecord::new(
-                        "BATAJNIČKI DRUM",
-                        vec![
-                            Broj::Bez,
-                            Broj::from(BrojRange::from((261, 265))),
-                            Broj::from(BrojNumber::from(269)),
-                            Broj::from(BrojRange::from((283, 293))),
-                            Broj::from(BrojNumber::from(299)),
-                            Broj::from(BrojRange::from(((303, None), (303, Some("A"))))),
-                        ]
-                    ),
-                ]
-            )
-        );
+        for broj in self.numbers.iter() {
+            match broj {
+                Broj::Bez | Broj::Letter(_) => {}
+                Broj::Number(number) | Broj::OpenRange(number) => check(number)?,
+                Broj::Range(range) => {
+                    check(&range.from)?;
+                    check(&range.to)?;
+                    let span = range.to.value.saturating_sub(range.from.value);
+                    if let Some(max) = rules.max_span.filter(|max| span > *max) {
+                        return Err(ValidationError::RangeTooWide {
+                            street: self.street.to_string(),
+                            range: range.to_string(),
+                            max,
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a> From<(&'a str, Vec<Broj<'a>>)> for AddressRecord<'a> {
+    fn from((street, numbers): (&'a str, Vec<Broj<'a>>)) -> Self {
+        Self::new(street, numbers)
     }
+}
 
-    #[test]
-    fn test_full_row_test() {
-        static TEST_INPUT: &str = "AUTOPUT ZA NOVI SAD: BB,284,294-296F,  BATAJNIČKI DRUM: BB,261-265,269,283-293,299,303-303A,  BATAJNIČKI DRUM 14 DEO: 14,  NIKOLE SUKNJAREVIĆA PRIKE: 2-18,1-17, NASELJE BATAJNICA:   1 SREMSKOG ODREDA: 2-90,1-89,  AERODROMSKA: 68A-80,84-88I,98,1-1A,5-13,23A,  BANOVAČKA: 4A-12,20,24,28,34,1-1A,  BATAJNIČKIH ŽRTAVA: 2-16,1-13,  BATINSKE BITKE: 2-60,1-59,  BEČEJSKA: 22-26,30-32,42-44,  BIHAĆKA: 2-28,1-7,  BOJČINSKA: 1-15,  BOSANSKE KRAJINE: 2-86,1-73,  BRAĆE BARIŠIĆA: 2-18,1-3,7-19,  BRAĆE MIHAJLOVIĆ-TRIPIĆ: 6-106,43-45,49-51,  BRAĆE NEŠTINAC: 2-12,1-11,  BRAĆE RADIŠIĆ: 4A-4V,1-41,  BRAĆE SAVIĆA: 2-54,1-73,  BRAĆE SMILJANIĆA: 4-6,14-64,68-72,3-11,15-61A,65A-71,75B-83F/3,  BRAĆE VOJINOVIĆA: 1-1A,5-7A,11-11A,15-15A,19-19A,  BRANISLAVA BARIŠIĆA: 2-92,1-43,47,53-57,  BRILETOVA: 2-6,1-5,  BRODSKA: 2-18,1-19,  CARICE JELENE : 2-26,1-27,  DALMATINSKE ZAGORE : 8-16,20-160,1-145B,  DALMATINSKIH BRIGADA: 4A-6A,10-12,20,24,36A/1-56,60,19-21A,25-33,39-43,47,51,57-57,61-85,89-91,97-103,  DESPOTA IVANIŠA : 10-12,18-24,  DIMITRIJA LAZAROVA RAŠE: 2-28,1-33,37-41,  DISKONT PKB NOVA 21: 2-14,17,  ĐORĐA BOŠKOVIĆA - BATE: 6,12-16B,26-36A,42-54,3-19B,23-39,43-63B,  DRAGE MIHAJLOVIĆA: 2-58,1-47,51-53,  ĐURĐA BALŠIĆA : 2-6,10-20,3,9,  ISLAMA GRČKOG: 4,8,12-18,17,21-31,  IVANA DELNEGRA-ENGLEZA : 2-42,1-17,  IVANA SENKOVIĆA: 2-78,1-73,  JOVANA BRANKOVIĆA : 2-118,122-152,156-166C,170,174-176D,180-182,1-137,141-155,161-171,  KARLOVČIĆKA: 2-6,1-5,  KATICE OPAČIĆ: 2-18,22-40,44-46,50,72,76,94-94D,98-104D,1,5-11,17-17,25A-69B,  KESARA HRELJE : 2,12-24,28,34-40,  KESARA NOVAKA : 2-14A,  KESARA PRELJUBA : 4-8,12,20,24-26,30-36,3-9,13-25,  KESARA VOJIHNE : 4-6,3-23,27-33,  KLISINA NOVA  8: 2,3,7-17,  KLISINA NOVA  9: 2A,6,10,14,18-20,3-5,9-9A,13-17,  KNEZA PASKAČA : 2-14,18,1-5,  KRALJA MIHAILA ZETSKOG : 2-4,8-24,30-32,48-52,1-11,45-47,51-67O,73-83,87,  KRALJA RADOSLAVA : 38-120,126-148,152-178,53-81,85-85,99-99N,105-181,  KRALJA STEFANA TOMAŠA : 40-42,48-58,64-66,67-89,  KRALJA UROŠA PRVOG : 2-16G,1,9A,  KRALJA VLADISLAVA : 22-42,46-50B,54-102,106,110-116,120-150,13-29,33-35,39-43,47-61,65-73,77-117,121-129,133-139,  KULSKA: 23-29E,  MAJKE JUGOVIĆA: 16-16A,30-36,11-11E,99N,  MAJORA ZORANA RADOSAVLJEVIĆA : 2-50,116-226,236-258B,262-290,372-374,382,1-49,117-143,149-277,281,  MAKSIMA BRANKOVIĆA : 2-26,30,38-56,1-3,7-47,  MALA: 2-10,1,  MARKA PERIČINA-KAMENJARA : 2-8A,16,24-26,32,42-70,1,25,39-43,  MATROZOVA: BB,  MIHALJEVAČKA: 2-20,1-19,  MILICE RAKIĆ : 2-96,3-21,39-79,83-117,  MITROVAČKA: 2-26,1-27,  MRCINIŠTE NOVA 28: 2-10,14-16,24-36,3-27,  NATALIJE DUBAJIĆ: 2-6A,1-11,  NIKICE POPOVIĆA: 2-18,1-13,  NOVAKA ATANACKOVIĆA: 2-6,1-3,  NOVOSADSKA : 10-98,1-41,45-47,51-61D,65-75Ž,81D-81E,97G-99J,103A-109V,  OFICIRSKA KOLONIJA : 4-10,14-16,1-9,13-17,  PALIĆKA: 2-52,1-83,  PEĆINAČKA: 2-76,1-39,  PILOTSKA: 2-20,1-19,  PRIMOŠTENSKA: 3,11,19-21,  PUKOVNIKA MILENKA PAVLOVIĆA : 2-142,160-162,180,1-9A,13-127,143-159A,175,  RATARSKA: 2-42,1-39,  ROMSKA: 2,14-16,23,  SAVE GRKINIĆA: 2-30,1-33,43,  SAVE RADOVANOVIĆA: 2-2A,6-8A,12-12A,16,20-20A,1-5,15-17,  SEVASTOKRATORA BRANKA : 2-90,1-89,  SEVASTOKRATORA DEJANA : 2-36,40,1,9-43,47-49,  SEVASTOKRATORA VLATKA : 2-68,1-79,  ŠIMANOVAČKA: 2-80,1-55,  ŠIROKI PUT: 2-16A,36,1-19,31E-31K,  ŠKOLSKA: 2-6,1-5,  SLOBODANA MACURE : 2-4,8-12,1-15,33-37,41-69,  SREMSKOG FRONTA: 2-20,1-9,13-25,  STANKA TIŠME: 2-84G,31A-47,71B-85V,  STEVANA DUBAJIĆA : 2A-42,46-48,52-68,74-82,1-17,21-29,33-73,79-81,85-91,  STEVE STANKOVIĆA: 2-18,1-11,  STOJANA BOŠKOVIĆA : 1,5-17,  SUNCOKRETA: 2-6,10-14,20,24,28-30,  SVETISLAVA VULOVIĆA : 4,10,14-18,27-33,37,  SVETOG RAFAILA ŠIŠATOVAČKOG : 2-12,1-15,  SVETOG SERAFIMA SAROVSKOG : 2-12,1-15,  SVILAJSKA: 2-12,1-9,  TITELSKA: 10-12,18-20,  VASILIJA RANKOVIĆA-BAĆE: 2-12,1-5,9-19,  VERE MIŠČEVIĆ: 2-30,5-15,19-33,  VOJVOĐANSKIH BRIGADA: 2-34,44-134,1-37,41-87,91-139A,143-145Z,  VOJVODE JAKŠE : 2-10,  VOJVODE NOVAKA : 2-6,10,44-46E,1-29F,33-39V,55M-55N,61B,  VOJVODE VOJISLAVA VOJINOVIĆA : 2-16,1-9,  VOJVODE VRATKA : BB,4-28,1A-37,  ŽARKA BOKUNA: 2-104,108-110,1-11,49,61-103,121-129,  ŽARKA OBREŠKOG: 2-14,18-20,30,34-34,38-40,44,1A-29E,33-41B,  ŽIKE MARKOVIĆA: 2-10,1-13,  ŽUPANA PRIBILA : 2-36,1-31, NASELJE ZEMUN:   BATAJNIČKI DRUM 13 DEO: 301,  KLISINA NOVA 10: 8-10,  TEMERINSKA 1 DEO: 1,";
-        let res = addresses(TEST_INPUT);
-        assert!(res.is_ok())
+/// Writes the record the way the source lists it, as `MALA: 2-10,1`. A
+/// street without numbers is written alone.
+impl<'a> fmt::Display for AddressRecord<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.street)?;
+        if let Some(note) = &self.note {
+            write!(f, " ({note})")?;
+        }
+        if !self.numbers.is_empty() {
+            write!(f, ": {}", self.numbers.iter().join(","))?;
+        }
+        Ok(())
+    }
+}
+
+/// Builds an [`AddressRecord`] number by number.
+///
+/// ```
+/// use beobot::addresses::AddressRecordBuilder;
+///
+/// let record = AddressRecordBuilder::default()
+///     .street("MALA")
+///     .bez()
+///     .range(2, 10)
+///     .number_ext(36, "A")
+///     .build();
+/// ```
+#[derive(Default, Debug)]
+pub struct AddressRecordBuilder<'a> {
+    street: &'a str,
+    numbers: Vec<Broj<'a>>,
+    settlement: Option<&'a str>,
+}
+
+impl<'a> AddressRecordBuilder<'a> {
+    pub fn street(mut self, street: &'a str) -> Self {
+        self.street = street;
+        self
+    }
+
+    pub fn number(mut self, value: usize) -> Self {
+        self.numbers.push(Broj::from(BrojNumber::from(value)));
+        self
+    }
+
+    pub fn number_ext(mut self, value: usize, extension: &'a str) -> Self {
+        self.numbers
+            .push(Broj::from(BrojNumber::from((value, Some(extension)))));
+        self
+    }
+
+    pub fn range(mut self, from: usize, to: usize) -> Self {
+        self.numbers.push(Broj::from(BrojRange::from((from, to))));
+        self
+    }
+
+    pub fn bez(mut self) -> Self {
+        self.numbers.push(Broj::Bez);
+        self
+    }
+
+    pub fn letter(mut self, letter: &'a str) -> Self {
+        self.numbers.push(Broj::Letter(Cow::Borrowed(letter)));
+        self
+    }
+
+    pub fn settlement(mut self, settlement: &'a str) -> Self {
+        self.settlement = Some(settlement);
+        self
+    }
+
+    pub fn build(self) -> AddressRecord<'a> {
+        let record = AddressRecord::new(self.street, self.numbers);
+        match self.settlement {
+            Some(settlement) => record.with_settlement(settlement),
+            None => record,
+        }
+    }
+}
+
+/// The criteria of [`Addresses::query`]: a part of the street name and
+/// the house number, its extension and the side of the street. The unset
+/// criteria match any record.
+///
+/// ```
+/// use beobot::addresses::{AddressQuery, Addresses};
+///
+/// let addresses = Addresses::parse("MALA: 2-10,12A,  BRODSKA: 12,").unwrap();
+/// let query = AddressQuery::default().street("mal").number(12).extension("a");
+/// assert_eq!(addresses.query(&query).len(), 1);
+/// ```
+#[derive(Eq, PartialEq, Clone, Default, Debug)]
+pub struct AddressQuery {
+    street: Option<String>,
+    number: Option<usize>,
+    extension: Option<String>,
+    parity: Option<Parity>,
+}
+
+impl AddressQuery {
+    /// Matches the streets whose name contains the text, ignoring the case.
+    pub fn street(mut self, street: &str) -> Self {
+        self.street = Some(normalize_whitespace(street).to_uppercase());
+        self
+    }
+
+    /// Matches the records listing the house number, see
+    /// [`AddressRecord::contains`].
+    pub fn number(mut self, value: usize) -> Self {
+        self.number = Some(value);
+        self
+    }
+
+    /// The extension of the house number, as `A` of `12A`. It only
+    /// narrows the number down and is ignored without one.
+    pub fn extension(mut self, extension: &str) -> Self {
+        self.extension = Some(extension.to_owned());
+        self
+    }
+
+    /// Matches the records of the side of the street. With a number of
+    /// the other side nothing matches.
+    pub fn parity(mut self, parity: Parity) -> Self {
+        self.parity = Some(parity);
+        self
+    }
+
+    /// Tells whether the record meets all the criteria.
+    pub fn matches(&self, record: &AddressRecord<'_>) -> bool {
+        if let Some(street) = &self.street {
+            if !record.street.to_uppercase().contains(street.as_str()) {
+                return false;
+            }
+        }
+        match self.number {
+            Some(number) => {
+                self.parity
+                    .is_none_or(|parity| Parity::of(number) == parity)
+                    && record.contains(number, self.extension.as_deref())
+            }
+            None => self.parity.is_none_or(|parity| has_side(record, parity)),
+        }
+    }
+}
+
+/// Tells whether the record lists any number of the side of the street.
+fn has_side(record: &AddressRecord<'_>, parity: Parity) -> bool {
+    record.numbers.iter().any(|broj| match broj {
+        Broj::Bez | Broj::Letter(_) => false,
+        Broj::Number(n) => Parity::of(n.value) == parity,
+        Broj::OpenRange(_) => true,
+        Broj::Range(r) => match r.explicit_parity {
+            Some(side) => side == parity,
+            None => {
+                r.from.value < r.to.value
+                    || Parity::of(r.from.value) == parity
+                    || Parity::of(r.to.value) == parity
+            }
+        },
+    })
+}
+
+/// Trims the text and collapses internal runs of whitespace into a single
+/// space. The text is borrowed as is when there is nothing to collapse.
+fn normalize_whitespace(text: &str) -> Cow<'_, str> {
+    let text = text.trim();
+    let mut prev_space = false;
+    let normalized = text.chars().all(|c| {
+        let ok = (c == ' ' && !prev_space) || !c.is_whitespace();
+        prev_space = c.is_whitespace();
+        ok
+    });
+
+    if normalized {
+        Cow::Borrowed(text)
+    } else {
+        Cow::Owned(text.split_whitespace().join(" "))
+    }
+}
+
+/// Splits a note in parentheses off the end of the street name, as in
+/// `NOVOSADSKA (deo)`. Nested or unbalanced parentheses are left in the
+/// name as they are.
+fn split_note(street: &str) -> (&str, Option<&str>) {
+    let trimmed = street.trim_end();
+    let Some(open) = trimmed.strip_suffix(')').and_then(|rest| rest.rfind('(')) else {
+        return (street, None);
+    };
+    let (name, note) = (&trimmed[..open], &trimmed[open + 1..trimmed.len() - 1]);
+    if name.trim().is_empty()
+        || note.trim().is_empty()
+        || name.contains(['(', ')'])
+        || note.contains(['(', ')'])
+    {
+        return (street, None);
+    }
+    (name, Some(note.trim()))
+}
+
+/// Splits a numbered part off the end of the street name, as in
+/// `TEMERINSKA 1 DEO` or `DISKONT PKB NOVA 21`. The name has to be left
+/// with something before the part, so `NOVA 21` alone is a name.
+fn split_part(street: &str) -> (&str, Option<Part>) {
+    let mut words = street.rsplitn(3, ' ');
+    let (Some(last), Some(middle), Some(base)) = (words.next(), words.next(), words.next()) else {
+        return (street, None);
+    };
+    let part = if last.eq_ignore_ascii_case("DEO") {
+        middle.parse().ok().map(Part::Deo)
+    } else if middle.eq_ignore_ascii_case("NOVA") {
+        last.parse().ok().map(Part::Nova)
+    } else {
+        None
+    };
+    match part {
+        Some(part) if !base.trim().is_empty() => (base.trim_end(), Some(part)),
+        _ => (street, None),
+    }
+}
+
+/// Limits used by [`AddressRecord::validate_with`]. `max_span` is the
+/// widest range allowed, `None` lets any range through.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct ValidationRules {
+    pub max_number: usize,
+    pub max_span: Option<usize>,
+}
+
+impl Default for ValidationRules {
+    fn default() -> Self {
+        Self {
+            max_number: 10000,
+            max_span: Some(500),
+        }
+    }
+}
+
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub enum ValidationError {
+    ZeroNumber {
+        street: String,
+    },
+    NumberTooLarge {
+        street: String,
+        value: usize,
+        max: usize,
+    },
+    RangeTooWide {
+        street: String,
+        range: String,
+        max: usize,
+    },
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationError::ZeroNumber { street } => {
+                write!(f, "{street}: house number 0 is not valid")
+            }
+            ValidationError::NumberTooLarge { street, value, max } => {
+                write!(f, "{street}: house number {value} is above {max}")
+            }
+            ValidationError::RangeTooWide { street, range, max } => {
+                write!(f, "{street}: range {range} spans more than {max} numbers")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// Parser a regular address number with optional extension letter. The
+/// extension may be any letter, as `Ž` in `75Ž`.
+fn address_number(input: &str) -> IResult<&str, BrojNumber<'_>> {
+    let digit_parser = map_res(digit1, |s: &str| s.parse::<usize>());
+    let ext_parser = map(
+        recognize(pair(
+            take_while(char::is_alphabetic),
+            opt(pair(tag("/"), digit1)),
+        )),
+        |x: &str| if !x.is_empty() { Some(x) } else { None },
+    );
+    map(pair(digit_parser, ext_parser), |(value, extension)| {
+        BrojNumber {
+            value,
+            extension: extension.map(normalize_extension),
+        }
+    })(input)
+}
+
+/// Drops the insignificant leading zeros of the slash suffix of an
+/// extension, so `A/01` becomes `A/1`.
+fn normalize_extension(extension: &str) -> Cow<'_, str> {
+    match extension.split_once('/') {
+        Some((letters, digits)) if digits.len() > 1 && digits.starts_with('0') => {
+            let digits = digits.trim_start_matches('0');
+            let digits = if digits.is_empty() { "0" } else { digits };
+            Cow::Owned(format!("{letters}/{digits}"))
+        }
+        _ => Cow::Borrowed(extension),
+    }
+}
+
+/// Recognizes the side annotation of a range: `(parne)` for the even and
+/// `(neparne)` for the odd side of the street.
+fn parity_annotation(input: &str) -> IResult<&str, Parity> {
+    let side = alt((
+        value(Parity::Odd, tag_no_case("neparne")),
+        value(Parity::Even, tag_no_case("parne")),
+    ));
+    delimited(pair(multispace0, tag("(")), side, tag(")"))(input)
+}
+
+/// Parse a range of addresses, optionally annotated with its side.
+fn address_number_range(input: &str) -> IResult<&str, BrojRange<'_>> {
+    let parser = separated_pair(address_number, tag("-"), address_number);
+    map(
+        pair(map(parser, BrojRange::from), opt(parity_annotation)),
+        |(range, parity)| match parity {
+            Some(parity) => range.with_parity(parity),
+            None => range,
+        },
+    )(input)
+}
+
+/// The order of the entries of a record: `Bez` first, then the letters,
+/// the numbers and the ranges by where they start, and the open ranges.
+fn broj_order<'b>(broj: &'b Broj<'_>) -> (u8, &'b str, usize, Option<&'b str>) {
+    match broj {
+        Broj::Bez => (0, "", 0, None),
+        Broj::Letter(letter) => (1, letter.as_ref(), 0, None),
+        Broj::Number(number) => (2, "", number.value, number.extension.as_deref()),
+        Broj::Range(range) => (2, "", range.from.value, range.from.extension.as_deref()),
+        Broj::OpenRange(from) => (3, "", from.value, from.extension.as_deref()),
+    }
+}
+
+/// Splits the ascending values into the runs of values `step` apart,
+/// yielding the first and the last value of every run.
+fn runs(values: impl Iterator<Item = usize>, step: usize) -> Vec<(usize, usize)> {
+    let mut runs: Vec<(usize, usize)> = Vec::new();
+    for value in values {
+        match runs.last_mut() {
+            Some((_, last)) if *last + step == value => *last = value,
+            _ => runs.push((value, value)),
+        }
+    }
+    runs
+}
+
+/// Recognizes "bez broja" (no number) in any of the spellings found in the
+/// source data: `BB`, `BB.`, `B.B.` and the Cyrillic `Б.Б.`.
+/// The marker must be a whole token, so `BBC` is not taken for `BB`.
+fn bez_broja(input: &str) -> IResult<&str, Broj<'_>> {
+    let spellings = alt((
+        tag_no_case("b.b."),
+        tag_no_case("bb."),
+        tag_no_case("bb"),
+        tag_no_case("б.б."),
+    ));
+    value(
+        Broj::Bez,
+        terminated(spellings, not(satisfy(char::is_alphanumeric))),
+    )(input)
+}
+
+/// Parses an address number, a range of addresses or a special BB case.
+fn broj(input: &str) -> IResult<&str, Broj<'_>> {
+    let bb_parser = bez_broja;
+    let number_parser = map(address_number, Broj::from);
+    let range_parser = map(address_number_range, collapse_range);
+
+    alt((bb_parser, range_parser, open_range, number_parser, letter))(input)
+}
+
+/// Recognizes a range open on the upper end, as `120-`. The dash has to end
+/// the entry.
+fn open_range(input: &str) -> IResult<&str, Broj<'_>> {
+    map(
+        terminated(
+            address_number,
+            pair(tag("-"), not(satisfy(|c| !c.is_whitespace() && c != ','))),
+        ),
+        Broj::OpenRange,
+    )(input)
+}
+
+/// Recognizes a building designated by a single letter, as `A`. A longer
+/// word is not taken for a letter, so a street name is never a number.
+fn letter(input: &str) -> IResult<&str, Broj<'_>> {
+    map(
+        terminated(
+            recognize(satisfy(char::is_alphabetic)),
+            not(satisfy(char::is_alphanumeric)),
+        ),
+        |letter| Broj::Letter(Cow::Borrowed(letter)),
+    )(input)
+}
+
+/// A range from a number to itself, like `303-303`, is a single number.
+/// `303-303A` differs in the extension and stays a range.
+fn collapse_range(range: BrojRange<'_>) -> Broj<'_> {
+    if range.from == range.to {
+        Broj::from(range.from)
+    } else {
+        Broj::from(range)
+    }
+}
+
+/// Recognizes a list of numbers, ranges or BB cases separated with
+/// whitespace only, as `2 18 1 17`. The list has to be over by the end of
+/// the text or by a comma ending the record, so a comma list like
+/// `2 18,3` is not taken for one. The letters are left out: `12 A` is not
+/// a list, and an extension stays with its number as in `12A 14`.
+fn space_separated_list(input: &str) -> IResult<&str, Vec<Broj<'_>>> {
+    let item = alt((
+        bez_broja,
+        map(address_number_range, collapse_range),
+        map(address_number, Broj::from),
+    ));
+    let list_end = pair(
+        multispace0,
+        alt((eof, terminated(tag(","), alt((eof, multispace1))))),
+    );
+    terminated(
+        verify(separated_list1(multispace1, item), |list: &Vec<_>| {
+            list.len() > 1
+        }),
+        peek(list_end),
+    )(input)
+}
+
+/// Recognizes the rest of a street whose name starts with a number, as
+/// ` SREMSKOG ODREDA:` after the `1` of `1 SREMSKOG ODREDA` or
+/// `. OKTOBRA:` after the `13` of `13. OKTOBRA`. The first entry of its
+/// numbers has to be a whole one, see `whole_entry`, so a trailing note as
+/// `1 NAPOMENA: 2 SATA` is not taken for a street. The numbers after that
+/// entry are left for the street's own list.
+fn numbered_street_rest(input: &str) -> IResult<&str, &str> {
+    terminated(
+        recognize(tuple((
+            alt((recognize(pair(char('.'), multispace0)), multispace1)),
+            satisfy(char::is_alphabetic),
+            take_till(|c| c == ':' || c == ','),
+            tag(":"),
+        ))),
+        peek(whole_entry),
+    )(input)
+}
+
+/// Recognizes the text up to the next comma or the end of the text when
+/// it is a single entry or a whitespace separated list of numbers, as
+/// `2-90` of `2-90,1-89`. The entries after the comma are not parsed.
+fn whole_entry(input: &str) -> IResult<&str, &str> {
+    verify(take_till(|c| c == ','), |text: &str| {
+        let entry = alt((recognize(space_separated_list), recognize(broj)));
+        all_consuming(delimited(multispace0, entry, multispace0))(text).is_ok()
+    })(input)
+}
+
+/// Recognizes a list of numbers separated with commas. An entry after a
+/// comma which starts the name of the next street, as the `1` of
+/// `2-10,1,1 SREMSKOG ODREDA: 2-90`, ends the list instead.
+fn comma_separated_list(input: &str) -> IResult<&str, Vec<Broj<'_>>> {
+    map(
+        pair(
+            broj,
+            many0(preceded(
+                tag(","),
+                terminated(broj, not(numbered_street_rest)),
+            )),
+        ),
+        |(first, rest)| std::iter::once(first).chain(rest).collect(),
+    )(input)
+}
+
+/// Recognizes a list of addresses, ranges of addresses or special BB cases.
+/// The entries are separated with commas, see `comma_separated_list`, or
+/// with whitespace alone when there are no commas, see
+/// `space_separated_list`.
+fn broj_list(input: &str) -> IResult<&str, Vec<Broj<'_>>> {
+    let parser = alt((space_separated_list, comma_separated_list));
+    delimited(
+        multispace0,
+        // potentially we can simply skip the second element of the pair (the trailing comma)
+        map(pair(parser, opt(tag(","))), |(x, _)| x),
+        multispace0,
+    )(input)
+}
+
+/// Recognizes a list of numbers which is a whole, ended by a comma or the
+/// end of the text, unlike the `1` of `1 SREMSKOG ODREDA`.
+fn whole_broj_list(input: &str) -> IResult<&str, &str> {
+    verify(recognize(broj_list), |list: &str| {
+        list.len() == input.len() || list.trim_end().ends_with(',')
+    })(input)
+}
+
+/// Recognizes the header of the streets of a settlement, as
+/// `NASELJE BATAJNICA:` in `NASELJE BATAJNICA:   1 SREMSKOG ODREDA: 2-90`,
+/// and returns the name of the settlement. A settlement followed by its
+/// numbers is a street and not a header.
+fn settlement_header(input: &str) -> IResult<&str, &str> {
+    terminated(
+        map(
+            preceded(
+                tag_no_case("NASELJE "),
+                take_till1(|c| c == ':' || c == ','),
+            ),
+            str::trim,
+        ),
+        pair(tag(":"), not(whole_broj_list)),
+    )(input)
+}
+
+/// Recognizes a street name: the text up to the colon which is followed by
+/// a list of numbers. A stray colon inside the name, as in
+/// `ULICA: NOVA 21: 2-14`, stays a part of the street. The name never spans
+/// a comma, so a record with broken numbers is not glued to the next one.
+fn street_name(input: &str) -> IResult<&str, &str> {
+    for (pos, _) in input.match_indices(':') {
+        if input[..pos].contains(',') {
+            break;
+        }
+        if broj_list(&input[pos + 1..]).is_ok() {
+            return Ok((&input[pos..], &input[..pos]));
+        }
+    }
+    take_until(":")(input)
+}
+
+/// Recognizes a pair of an address and the list of addresses' numbers.
+fn address_number_pair(input: &str) -> IResult<&str, AddressRecord<'_>> {
+    map(
+        separated_pair(street_name, tag(":"), broj_list),
+        |(a, b)| AddressRecord::new(a.trim(), b),
+    )(input)
+}
+
+/// Parse addresses info (row). Records without a street name are skipped
+/// and reported as warnings. Streets listed without a colon and numbers,
+/// as `BRODSKA` in `MALA: 1,  BRODSKA,  PILOTSKA: 2-18`, are kept as
+/// records without numbers instead of being glued to the next street.
+/// The streets after a settlement header are put under the settlement.
+/// The records after the first one have to be ended by a comma or the end
+/// of the row, or followed by another record, so the parser stops before
+/// a trailing note as `NAPOMENA: 2 SATA`.
+fn addresses(input: &str) -> IResult<&str, (Vec<AddressRecord<'_>>, Vec<ParseWarning<'_>>)> {
+    addresses_under(None)(input)
+}
+
+/// Parses the records as `addresses` does, the streets before the first
+/// settlement header are put under the given settlement. Every record is
+/// parsed once: a record which is not ended by a comma is held back until
+/// the next one parses.
+fn addresses_under<'a>(
+    settlement: Option<Cow<'a, str>>,
+) -> impl FnMut(&'a str) -> IResult<&'a str, (Vec<AddressRecord<'a>>, Vec<ParseWarning<'a>>)> {
+    move |input| {
+        let mut record = pair(
+            preceded(multispace0, opt(terminated(settlement_header, multispace0))),
+            consumed(address_number_pair),
+        );
+        let mut row = RowRecords::new(settlement.clone());
+        let (mut rest, first) = record(input)?;
+        row.push(first);
+        // the end of the last record taken, and the record after it which
+        // is taken only if another record follows it
+        let mut end = rest;
+        let mut held = None;
+        loop {
+            let (after, next) = match record(rest) {
+                Ok(parsed) => parsed,
+                Err(Err::Error(_)) => break,
+                Err(err) => return Err(err),
+            };
+            if let Some(previous) = held.take() {
+                row.push(previous);
+                end = rest;
+            }
+            let (_, (raw, _)) = &next;
+            if after.is_empty() || raw.trim_end().ends_with(',') {
+                row.push(next);
+                end = after;
+            } else {
+                held = Some(next);
+            }
+            rest = after;
+        }
+        Ok((end, (row.items, row.warnings)))
+    }
+}
+
+/// The records of a row as they are parsed, along with the settlement of
+/// the last header.
+struct RowRecords<'a> {
+    items: Vec<AddressRecord<'a>>,
+    warnings: Vec<ParseWarning<'a>>,
+    settlement: Option<Cow<'a, str>>,
+}
+
+impl<'a> RowRecords<'a> {
+    fn new(settlement: Option<Cow<'a, str>>) -> Self {
+        Self {
+            items: Vec::new(),
+            warnings: Vec::new(),
+            settlement,
+        }
+    }
+
+    /// Takes the record along with the settlement header before it and
+    /// the text it is parsed from.
+    fn push(
+        &mut self,
+        (header, (raw, mut record)): (Option<&'a str>, (&'a str, AddressRecord<'a>)),
+    ) {
+        if let Some(header) = header {
+            self.settlement = Some(normalize_whitespace(header));
+        }
+        let settlement = self.settlement.clone();
+        let under = |record: AddressRecord<'a>| AddressRecord {
+            settlement: settlement.clone(),
+            ..record
+        };
+        if record.street.contains(',') {
+            // such a street ends at the first colon, see `street_name`
+            let raw_street = raw.split(':').next().unwrap_or_default().trim();
+            let mut names = raw_street
+                .split(',')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .collect::<Vec<_>>();
+            let last = names.pop().unwrap_or_default();
+            if names.iter().all(|name| is_bare_street(name)) {
+                self.warnings
+                    .push(ParseWarning::MissingColon(Cow::Borrowed(raw_street)));
+                self.items.extend(
+                    names
+                        .into_iter()
+                        .map(|name| under(AddressRecord::new(name, Vec::new()))),
+                );
+                record = AddressRecord::new(last, record.numbers);
+            }
+        }
+
+        if record.street.is_empty() {
+            self.warnings
+                .push(ParseWarning::EmptyStreet(Cow::Borrowed(raw.trim())));
+        } else {
+            self.items.push(under(record));
+        }
+    }
+}
+
+/// Tells whether the comma separated piece of a row is a street listed
+/// without a colon and numbers rather than broken numbers.
+fn is_bare_street(piece: &str) -> bool {
+    let piece = piece.trim_start();
+    !piece.is_empty()
+        && !piece.starts_with(|c: char| c.is_ascii_digit())
+        && all_consuming(broj_list)(piece).is_err()
+}
+
+/// Problems found in the input which did not stop the parsing.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub enum ParseWarning<'a> {
+    /// A record without a street name was skipped, holds the record text.
+    EmptyStreet(Cow<'a, str>),
+    /// Streets without numbers were listed without a colon before the next
+    /// street, holds the text of the streets.
+    MissingColon(Cow<'a, str>),
+    /// The parser stopped before the end of the row, holds the rest of the
+    /// row which was left out.
+    UnparsedTail(Cow<'a, str>),
+}
+
+impl<'a> ParseWarning<'a> {
+    /// Copies the borrowed text of the warning.
+    pub fn into_owned(self) -> ParseWarning<'static> {
+        match self {
+            ParseWarning::EmptyStreet(raw) => ParseWarning::EmptyStreet(into_owned_text(raw)),
+            ParseWarning::MissingColon(raw) => ParseWarning::MissingColon(into_owned_text(raw)),
+            ParseWarning::UnparsedTail(raw) => ParseWarning::UnparsedTail(into_owned_text(raw)),
+        }
+    }
+}
+
+impl<'a> fmt::Display for ParseWarning<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseWarning::EmptyStreet(raw) => {
+                write!(f, "skipped a record without a street name: \"{raw}\"")
+            }
+            ParseWarning::MissingColon(raw) => {
+                write!(f, "streets listed without a colon: \"{raw}\"")
+            }
+            ParseWarning::UnparsedTail(raw) => {
+                write!(f, "left out the unparsed end of the row: \"{raw}\"")
+            }
+        }
+    }
+}
+
+/// A part of the row which could not be parsed into records.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct ParseError<'a> {
+    chunk: &'a str,
+}
+
+impl<'a> ParseError<'a> {
+    /// The text of the rejected `street: numbers` chunk.
+    pub fn chunk(&self) -> &'a str {
+        self.chunk
+    }
+}
+
+impl<'a> fmt::Display for ParseError<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "cannot parse the record \"{}\"", self.chunk)
+    }
+}
+
+impl<'a> std::error::Error for ParseError<'a> {}
+
+/// Splits a row into the candidate `street: numbers` chunks. Street names
+/// never contain a comma, so a chunk starts at a comma separated piece
+/// which has a colon in it. A piece which is neither numbers nor has a colon
+/// is a street listed without numbers, it starts a chunk which runs up to
+/// the end of the next street.
+fn record_chunks(input: &str) -> impl Iterator<Item = &str> {
+    let mut starts = Vec::new();
+    let mut offset = 0;
+    // whether the current chunk has got to its numbers
+    let mut has_colon = false;
+    for piece in input.split_inclusive(',') {
+        if offset == 0 {
+            starts.push(offset);
+            has_colon = piece.contains(':');
+        } else if piece.contains(':') {
+            if has_colon {
+                starts.push(offset);
+            }
+            has_colon = true;
+        } else if has_colon && is_bare_street(piece) {
+            starts.push(offset);
+            has_colon = false;
+        }
+        offset += piece.len();
+    }
+    starts.push(input.len());
+
+    starts
+        .into_iter()
+        .tuple_windows()
+        .map(move |(from, to)| &input[from..to])
+        .filter(|chunk| !chunk.trim().is_empty())
+}
+
+#[derive(Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct Addresses<'a> {
+    #[cfg_attr(feature = "serde", serde(borrow))]
+    items: Vec<AddressRecord<'a>>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    warnings: Vec<ParseWarning<'a>>,
+}
+
+impl<'a> Addresses<'a> {
+    /// Copies the borrowed records and warnings, so the addresses outlive
+    /// the parsed row, e.g. the page body they come from.
+    pub fn into_owned(self) -> Addresses<'static> {
+        Addresses {
+            items: self
+                .items
+                .into_iter()
+                .map(AddressRecord::into_owned)
+                .collect(),
+            warnings: self
+                .warnings
+                .into_iter()
+                .map(ParseWarning::into_owned)
+                .collect(),
+        }
+    }
+
+    /// Parses the row from the start for as long as it is made of records.
+    /// If the parser stops early, the rest of the row is reported with a
+    /// [`ParseWarning::UnparsedTail`] warning instead of being dropped.
+    pub fn parse(input: &'a str) -> Result<Addresses<'a>, Err<Error<&'a str>>> {
+        let (rest, (items, mut warnings)) = addresses(input)?;
+        let rest = rest.trim();
+        if !rest.is_empty() {
+            warnings.push(ParseWarning::UnparsedTail(Cow::Borrowed(rest)));
+        }
+        Ok(Self { items, warnings })
+    }
+
+    /// Parses every `street: numbers` chunk of the row on its own, so a
+    /// broken record does not cost the rest of the row. The records of the
+    /// good chunks are returned along with an error per rejected chunk.
+    /// A settlement header carries over to the chunks after it.
+    pub fn parse_lenient(input: &'a str) -> (Addresses<'a>, Vec<ParseError<'a>>) {
+        let mut items = Vec::new();
+        let mut warnings = Vec::new();
+        let mut errors = Vec::new();
+        let mut settlement = None;
+
+        for chunk in record_chunks(input) {
+            match all_consuming(addresses_under(settlement.clone()))(chunk) {
+                Ok((_, (chunk_items, chunk_warnings))) => {
+                    if let Some(last) = chunk_items.last() {
+                        settlement = last.settlement.clone();
+                    }
+                    items.extend(chunk_items);
+                    warnings.extend(chunk_warnings);
+                }
+                Err(_) => errors.push(ParseError {
+                    chunk: chunk.trim(),
+                }),
+            }
+        }
+
+        (Self { items, warnings }, errors)
+    }
+
+    /// The problems which were skipped over while parsing.
+    pub fn warnings(&self) -> &[ParseWarning<'a>] {
+        &self.warnings
+    }
+
+    /// A copy with the records ordered by the street name and the numbers
+    /// of every record in order, `Bez` and the letters first, for output
+    /// which does not depend on the order of the source. The records of
+    /// the same street keep their order.
+    pub fn sorted(&self) -> Self {
+        let mut sorted = self.clone();
+        sorted.items.sort_by(|a, b| a.street.cmp(&b.street));
+        for record in sorted.items.iter_mut() {
+            record
+                .numbers
+                .sort_by(|a, b| broj_order(a).cmp(&broj_order(b)));
+        }
+        sorted
+    }
+
+    /// Merges the records of a street listed several times, as once per
+    /// settlement, into its first record: the numbers of the later ones
+    /// are appended in order. The streets are compared by the stored name,
+    /// so the note and the settlement of the first record are kept and the
+    /// others are dropped.
+    /// The numbers are not normalized, see [`AddressRecord::canonical`].
+    pub fn merge_duplicate_streets(&mut self) {
+        let mut merged: Vec<AddressRecord<'a>> = Vec::with_capacity(self.items.len());
+        for record in self.items.drain(..) {
+            match merged
+                .iter_mut()
+                .find(|first| first.street == record.street)
+            {
+                Some(first) => first.numbers.extend(record.numbers),
+                None => merged.push(record),
+            }
+        }
+        self.items = merged;
+    }
+
+    /// Finds the record of the street by its exact name, as it is stored:
+    /// transliterated and with the whitespace collapsed. A street listed
+    /// several times yields its first record, see [`Addresses::get_all`].
+    pub fn get(&self, street: &str) -> Option<&AddressRecord<'a>> {
+        self.get_all(street).next()
+    }
+
+    /// Yields every record of the street, in the order of the row.
+    pub fn get_all(&self, street: &str) -> impl Iterator<Item = &AddressRecord<'a>> + '_ {
+        let street = normalize_whitespace(street).into_owned();
+        self.items
+            .iter()
+            .filter(move |record| record.street == street)
+    }
+
+    /// Yields every record of the street along with the records of its
+    /// numbered parts, as `BATAJNIČKI DRUM 14 DEO` for `BATAJNIČKI DRUM`,
+    /// in the order of the row.
+    pub fn get_with_parts(&self, street: &str) -> impl Iterator<Item = &AddressRecord<'a>> + '_ {
+        let street = normalize_whitespace(street).into_owned();
+        self.items
+            .iter()
+            .filter(move |record| record.base() == street)
+    }
+
+    /// Lists the records meeting all the criteria of the query, in the
+    /// order of the row.
+    pub fn query(&self, query: &AddressQuery) -> Vec<&AddressRecord<'a>> {
+        self.items
+            .iter()
+            .filter(|record| query.matches(record))
+            .collect()
+    }
+
+    /// Counts the distinct streets of the row, a street listed in several
+    /// records counts once.
+    pub fn street_count(&self) -> usize {
+        self.items
+            .iter()
+            .map(|record| record.street())
+            .collect::<BTreeSet<_>>()
+            .len()
+    }
+
+    /// Counts the affected house numbers: every number of every range plus
+    /// the single numbers. A `Bez` entry and a letter count as one building.
+    pub fn count_affected(&self) -> usize {
+        self.items
+            .iter()
+            .flat_map(|record| record.numbers.iter())
+            .map(|broj| match broj {
+                Broj::Bez | Broj::Letter(_) => 1,
+                broj => broj.expand().count(),
+            })
+            .sum()
+    }
+
+    /// Same as [`Addresses::count_affected`] but a house number listed
+    /// several times within one record (e.g. inside overlapping ranges),
+    /// or several `Bez` entries or the same letter of one record, are
+    /// counted once.
+    pub fn count_affected_distinct(&self) -> usize {
+        self.items
+            .iter()
+            .map(|record| {
+                let has_bez = record.has_bez();
+                let letters = record
+                    .numbers
+                    .iter()
+                    .filter_map(|broj| match broj {
+                        Broj::Letter(letter) => Some(letter.as_ref()),
+                        _ => None,
+                    })
+                    .collect::<BTreeSet<_>>();
+                let numbers = record
+                    .numbers
+                    .iter()
+                    .flat_map(Broj::expand)
+                    .map(|number| (number.value, number.extension))
+                    .collect::<BTreeSet<_>>();
+                numbers.len() + letters.len() + usize::from(has_bez)
+            })
+            .sum()
+    }
+
+    /// Pairs every street with each of its house numbers, ranges are
+    /// expanded. `Bez` entries have no number and are skipped.
+    pub fn flatten(&self) -> impl Iterator<Item = (&str, BrojNumber<'a>)> + '_ {
+        self.items.iter().flat_map(|record| {
+            let street = record.street.as_ref();
+            record
+                .numbers
+                .iter()
+                .flat_map(Broj::expand)
+                .map(move |number| (street, number))
+        })
+    }
+}
+
+impl<'a> IntoIterator for Addresses<'a> {
+    type Item = <Vec<AddressRecord<'a>> as IntoIterator>::Item;
+    type IntoIter = <Vec<AddressRecord<'a>> as IntoIterator>::IntoIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.into_iter()
+    }
+}
+
+impl<'a, 'b> IntoIterator for &'b Addresses<'a> {
+    type Item = <&'b Vec<AddressRecord<'a>> as IntoIterator>::Item;
+    type IntoIter = <&'b Vec<AddressRecord<'a>> as IntoIterator>::IntoIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.iter()
+    }
+}
+
+/// Writes the records separated the way the source separates them, so
+/// the text parses back into the same records. A settlement header holds
+/// for the rest of the row, so the records without a settlement are
+/// written ahead of the others.
+impl<'a> fmt::Display for Addresses<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (unsettled, settled): (Vec<_>, Vec<_>) = self
+            .items
+            .iter()
+            .partition(|record| record.settlement.is_none());
+        let mut settlement = None;
+        for (i, record) in unsettled.into_iter().chain(settled).enumerate() {
+            if i > 0 {
+                f.write_str(",  ")?;
+            }
+            if record.settlement.is_some() && record.settlement != settlement {
+                settlement = record.settlement.clone();
+                write!(f, "NASELJE {}:   ", record.settlement().unwrap_or_default())?;
+            }
+            write!(f, "{record}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_can_parse_complicated_address() {
+        let res = address_number("36A/1").expect("parse the compilcated regular address");
+        assert_eq!(res, ("", BrojNumber::from((36, Some("A/1")))));
+    }
+
+    #[test]
+    fn test_strips_leading_zeros() {
+        let res = address_number("05A/01").expect("parse the zero padded address");
+        assert_eq!(res, ("", BrojNumber::from((5, Some("A/1")))));
+
+        let res = address_number("007/00").expect("parse the zero padded address");
+        assert_eq!(res, ("", BrojNumber::from((7, Some("/0")))));
+
+        let res = address_number("36A/1").expect("parse the address");
+        assert_eq!(res, ("", BrojNumber::from((36, Some("A/1")))));
+        assert!(matches!(res.1.extension, Some(Cow::Borrowed("A/1"))));
+    }
+
+    #[test]
+    fn test_can_parse_a_range_of_addresses() {
+        let res = address_number_range("123-321").expect("parse the range of addresses");
+        assert_eq!(res, ("", BrojRange::from((123, 321))))
+    }
+
+    #[test]
+    fn test_can_parse_a_range_with_extension_on_one_end() {
+        let res = address_number_range("5A-13").expect("parse the range");
+        assert_eq!(res, ("", BrojRange::from(((5, Some("A")), (13, None)))));
+
+        let res = address_number_range("65A-71").expect("parse the range");
+        assert_eq!(res, ("", BrojRange::from(((65, Some("A")), (71, None)))));
+
+        let res = address_number_range("75B-83F/3").expect("parse the range");
+        assert_eq!(
+            res,
+            ("", BrojRange::from(((75, Some("B")), (83, Some("F/3")))))
+        );
+    }
+
+    #[test]
+    fn test_broj_display() {
+        assert_eq!(Broj::Bez.to_string(), "BB");
+        assert_eq!(
+            Broj::from(BrojNumber::from((36, Some("A/1")))).to_string(),
+            "36A/1"
+        );
+        assert_eq!(
+            Broj::from(BrojRange::from(((294, None), (296, Some("F"))))).to_string(),
+            "294-296F"
+        );
+    }
+
+    #[test]
+    fn test_can_parse_one_of() {
+        let res = broj("BB").expect("can recognize BB");
+        assert_eq!(res, ("", Broj::Bez));
+
+        let res = broj("123A").expect("can recognize an address number");
+        assert_eq!(res, ("", Broj::from(BrojNumber::from((123, Some("A"))))));
+
+        let res = broj("123A-321B").expect("can recognize an addresses range");
+        assert_eq!(
+            res,
+            (
+                "",
+                Broj::from(BrojRange::from(((123, Some("A")), (321, Some("B")))))
+            )
+        );
+    }
+
+    #[test]
+    fn test_can_parse_bez_broja_spellings() {
+        for input in ["BB", "bb", "BB.", "b.b.", "B.B.", "б.б.", "Б.Б."] {
+            let res = broj(input).expect("can recognize bez broja");
+            assert_eq!(res, ("", Broj::Bez), "spelling {input:?}");
+        }
+
+        let res = broj_list("B.B.,12").expect("parse bez broja followed by a number");
+        assert_eq!(
+            res,
+            ("", vec![Broj::Bez, Broj::Number(BrojNumber::from(12))])
+        );
+    }
+
+    #[test]
+    fn test_bez_broja_is_a_whole_token() {
+        assert!(broj("BBC").is_err());
+
+        let (_, record) = address_number_pair("ABBA: BB,1").expect("parse the record");
+        assert_eq!(
+            record,
+            AddressRecord::new("ABBA", vec![Broj::Bez, Broj::Number(BrojNumber::from(1))])
+        );
+    }
+
+    #[test]
+    fn test_can_parse_numbers_sequences() {
+        let res = broj_list("BB,123,123-321").expect("parse the sequence of numbers");
+        assert_eq!(
+            res,
+            (
+                "",
+                vec![
+                    Broj::Bez,
+                    Broj::Number(BrojNumber::from(123)),
+                    Broj::Range(BrojRange::from((123, 321))),
+                ]
+            )
+        )
+    }
+
+    #[test]
+    fn test_ignores_trailing_whitespaces() {
+        let res =
+            broj_list("   BB,BB   ").expect("rejects whitespaces before and after the sequence");
+        assert_eq!(res, ("", vec![Broj::Bez, Broj::Bez,]))
+    }
+
+    #[test]
+    fn test_can_recognize_trailing_comma() {
+        let res = broj_list("BB,")
+            .expect("parse the sequence of addresses followed by the trailing comma");
+        assert_eq!(res, ("", vec![Broj::Bez,]));
+    }
+
+    #[test]
+    fn test_reject_simple_comma() {
+        let res = broj_list("   ,   ");
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn test_parse() {
+        let res = address_number_pair("  AUTOPUT ZA NOVI SAD  : BB,284,294-296F,").unwrap();
+        assert_eq!(
+            res,
+            (
+                "",
+                AddressRecord::new(
+                    "AUTOPUT ZA NOVI SAD",
+                    vec![
+                        Broj::Bez,
+                        Broj::Number(BrojNumber::from(284)),
+                        Broj::Range(BrojRange::from(((294, None), (296, Some("F"))))),
+                    ]
+                )
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_all_addresses() {
+        let res = addresses("AUTOPUT ZA NOVI SAD: BB,284,294-296F,  BATAJNIČKI DRUM: BB,261-265,269,283-293,299,303-303A,").expect("parse the address row");
+
+        assert_eq!(
+            res,
+            (
+                "",
+                (
+                    vec![
+                        AddressRecord::new(
+                            "AUTOPUT ZA NOVI SAD",
+                            vec![
+                                Broj::Bez,
+                                Broj::from(BrojNumber::from(284)),
+                                Broj::from(BrojRange::from(((294, None), (296, Some("F"))))),
+                            ]
+                        ),
+                        AddressRecord::new(
+                            "BATAJNIČKI DRUM",
+                            vec![
+                                Broj::Bez,
+                                Broj::from(BrojRange::from((261, 265))),
+                                Broj::from(BrojNumber::from(269)),
+                                Broj::from(BrojRange::from((283, 293))),
+                                Broj::from(BrojNumber::from(299)),
+                                Broj::from(BrojRange::from(((303, None), (303, Some("A"))))),
+                            ]
+                        ),
+                    ],
+                    vec![]
+                )
+            )
+        );
+    }
+
+    #[test]
+    fn test_accessors() {
+        let record = AddressRecord::from_raw("AUTOPUT ZA NOVI SAD", "BB,284,294-296F").unwrap();
+        assert_eq!(record.street(), "AUTOPUT ZA NOVI SAD");
+        assert!(!record.is_whole_street());
+        let [Broj::Bez, Broj::Number(number), Broj::Range(range)] = record.numbers() else {
+            panic!("unexpected numbers {:?}", record.numbers());
+        };
+        assert_eq!((number.value(), number.extension()), (284, None));
+        assert_eq!(
+            (range.start().value(), range.start().extension()),
+            (294, None)
+        );
+        assert_eq!(
+            (range.end().value(), range.end().extension()),
+            (296, Some("F"))
+        );
+
+        let (addresses, _) = Addresses::parse_lenient("MALA: 1,  BRODSKA,  PILOTSKA: 2-18,");
+        let whole = addresses
+            .into_iter()
+            .map(|record| (record.street().to_owned(), record.is_whole_street()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            whole,
+            [
+                ("MALA".to_owned(), false),
+                ("BRODSKA".to_owned(), true),
+                ("PILOTSKA".to_owned(), false)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_range_expand() {
+        let range = BrojRange::from(((294, None), (296, Some("F"))));
+        assert_eq!(
+            range.expand().collect::<Vec<_>>(),
+            vec![
+                BrojNumber::from(294),
+                BrojNumber::from(295),
+                BrojNumber::from((296, Some("F"))),
+            ]
+        );
+
+        let range = BrojRange::from(((303, None), (303, Some("A"))));
+        assert_eq!(
+            range.expand().collect::<Vec<_>>(),
+            vec![BrojNumber::from(303), BrojNumber::from((303, Some("A")))]
+        );
+
+        let range = BrojRange::from((5, 5));
+        assert_eq!(
+            range.expand().collect::<Vec<_>>(),
+            vec![BrojNumber::from(5)]
+        );
+    }
+
+    #[test]
+    fn test_flatten() {
+        let addresses = Addresses::parse("MALA: BB,123,125-127").expect("parse the row");
+        assert_eq!(
+            addresses.flatten().collect::<Vec<_>>(),
+            vec![
+                ("MALA", BrojNumber::from(123)),
+                ("MALA", BrojNumber::from(125)),
+                ("MALA", BrojNumber::from(126)),
+                ("MALA", BrojNumber::from(127)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_count_affected() {
+        let addresses =
+            Addresses::parse("MALA: BB,2-10,1,  BRODSKA: 2-6,4,3,BB,BB").expect("parse the row");
+        // MALA: 1 + 9 + 1, BRODSKA: 5 + 1 + 1 + 1 + 1
+        assert_eq!(addresses.count_affected(), 20);
+        // BRODSKA 3 and 4 are listed twice, BB is counted once per street
+        assert_eq!(addresses.count_affected_distinct(), 17);
+    }
+
+    #[test]
+    fn test_from_raw() {
+        let record = AddressRecord::from_raw("MALA", "2-10,1").expect("parse the numbers");
+        assert_eq!(record.street(), "MALA");
+        assert_eq!(
+            record.numbers(),
+            &[
+                Broj::from(BrojRange::from((2, 10))),
+                Broj::from(BrojNumber::from(1))
+            ]
+        );
+
+        let err = AddressRecord::from_raw("MALA", "2-10, X1").unwrap_err();
+        assert_eq!(err.chunk(), "2-10, X1");
+        assert!(AddressRecord::from_raw("MALA", "").is_err());
+    }
+
+    #[test]
+    fn test_records_in_hash_set() {
+        let numbers = vec![
+            Broj::Bez,
+            Broj::from(BrojRange::from((2, 10)).with_parity(Parity::Even)),
+            Broj::from(BrojNumber::from((36, Some("A/1")))),
+        ];
+        let mut records = std::collections::HashSet::new();
+        records.insert(AddressRecord::new("MALA", numbers.clone()));
+        records.insert(AddressRecord::new("MALA", numbers));
+        assert_eq!(records.len(), 1);
+
+        records.insert(AddressRecord::new("MALA", vec![Broj::Bez]));
+        assert_eq!(records.len(), 2);
+    }
+
+    #[test]
+    fn test_broj_try_from() {
+        assert_eq!(
+            "123A".try_into(),
+            Ok(Broj::from(BrojNumber::from((123, Some("A")))))
+        );
+        assert_eq!(Broj::try_from("BB"), Ok(Broj::Bez));
+        assert_eq!(
+            Broj::try_from("2-10"),
+            Ok(Broj::from(BrojRange::from((2, 10))))
+        );
+
+        let err = Broj::try_from("12-A").unwrap_err();
+        assert_eq!(err.chunk(), "12-A");
+        assert!(Broj::try_from("12,14").is_err());
+        assert!(Broj::try_from("").is_err());
+    }
+
+    #[test]
+    fn test_canonical() {
+        let canonical = |numbers: &str| {
+            let record = AddressRecord::from_raw("MALA", numbers).expect("parse the numbers");
+            record.canonical().iter().join(",")
+        };
+
+        assert_eq!(canonical("5,3,1,7"), "1-7 (neparne)");
+        assert_eq!(canonical("2,6,4"), "2-6 (parne)");
+        assert_eq!(canonical("4,1-3,2"), "1-4");
+        assert_eq!(canonical("9,BB,7A,2-6,6,10,12"), "BB,2-6,7A,9-10,12");
+        assert_eq!(canonical("15"), "15");
+
+        // the canonical form lists the same numbers
+        let record = AddressRecord::from_raw("MALA", "5,3,1,7,2,6,4,8A").unwrap();
+        let canonical = AddressRecord::new("MALA", record.canonical());
+        for number in 0..12 {
+            assert_eq!(
+                canonical.contains(number, None),
+                record.contains(number, None)
+            );
+        }
+        assert!(canonical.contains(8, Some("A")));
+    }
+
+    #[test]
+    fn test_letter_numbers() {
+        assert_eq!(
+            broj_list("A,B,12"),
+            Ok((
+                "",
+                vec![
+                    Broj::Letter("A".into()),
+                    Broj::Letter("B".into()),
+                    Broj::from(BrojNumber::from(12))
+                ]
+            ))
+        );
+        assert_eq!(Broj::try_from("Ž"), Ok(Broj::Letter("Ž".into())));
+        assert!(Broj::try_from("AB").is_err());
+        assert_eq!(Broj::try_from("BB"), Ok(Broj::Bez));
+
+        let res = Addresses::parse("MALA: A,B,12,  BRODSKA: 2,").expect("parse the row");
+        let record = res.items.first().expect("the first record");
+        assert_eq!(record.street(), "MALA");
+        assert!(record.contains(12, None));
+        assert_eq!(record.numbers().iter().join(","), "A,B,12");
+        assert_eq!(record.coverage(), Coverage::EvenOnly);
+        assert_eq!(res.count_affected(), 4);
+        assert_eq!(res.count_affected_distinct(), 4);
+        assert_eq!(
+            AddressRecord::from_raw("MALA", "12,B,A,B")
+                .unwrap()
+                .canonical(),
+            vec![
+                Broj::Letter("A".into()),
+                Broj::Letter("B".into()),
+                Broj::from(BrojNumber::from(12))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_open_range() {
+        assert_eq!(
+            Broj::try_from("120-"),
+            Ok(Broj::OpenRange(BrojNumber::from(120)))
+        );
+        assert_eq!(
+            broj_list("2-10,120-,"),
+            Ok((
+                "",
+                vec![
+                    Broj::from(BrojRange::from((2, 10))),
+                    Broj::OpenRange(BrojNumber::from(120))
+                ]
+            ))
+        );
+
+        let record = AddressRecord::from_raw("MALA", "1,120-").expect("parse the numbers");
+        assert!(record.contains(500, None));
+        assert!(record.contains(120, None));
+        assert!(!record.contains(119, None));
+        assert_eq!(record.coverage(), Coverage::Both);
+        assert_eq!(record.numbers()[1].to_string(), "120-");
+
+        let expanded = record.numbers()[1].expand().collect_vec();
+        assert_eq!(expanded.len(), OPEN_RANGE_CAP - 120 + 1);
+        assert_eq!(Broj::OpenRange(BrojNumber::from(5000)).expand().count(), 1);
+
+        let record = AddressRecord::from_raw("MALA", "130,3,120-,125-").unwrap();
+        assert_eq!(record.canonical().iter().join(","), "3,120-");
+    }
+
+    #[test]
+    fn test_record_accessors() {
+        let numbers = vec![Broj::Bez, Broj::from(BrojRange::from((2, 10)))];
+        let record = AddressRecord::new("MALA", numbers.clone());
+        assert_eq!(record.street(), "MALA");
+        assert_eq!(record.numbers(), numbers.as_slice());
+    }
+
+    #[test]
+    fn test_contains() {
+        let (_, record) = address_number_pair("MALA: BB,2-10,15,47A").expect("parse the record");
+        assert!(record.contains(2, None));
+        assert!(record.contains(7, None));
+        assert!(record.contains(7, Some("A")));
+        assert!(record.contains(15, None));
+        assert!(record.contains(47, Some("A")));
+        assert!(!record.contains(47, None));
+        assert!(!record.contains(15, Some("B")));
+        assert!(!record.contains(11, None));
+    }
+
+    #[test]
+    fn test_contains_ignores_extension_case() {
+        let (_, record) = address_number_pair("MALA: 47A,36b").expect("parse the record");
+        assert!(record.contains(47, Some("a")));
+        assert!(record.contains(47, Some("A")));
+        assert!(record.contains(36, Some("B")));
+        assert_eq!(
+            record.numbers()[1],
+            Broj::from(BrojNumber::from((36, Some("b"))))
+        );
+    }
+
+    #[test]
+    fn test_bez_alongside_numbers() {
+        let (_, record) =
+            address_number_pair("VOJVODE VRATKA : BB,4-28,1A-37,").expect("parse the record");
+        assert!(record.has_bez());
+        assert!(record.contains(10, None));
+        assert!(!record.contains(40, None));
+
+        let (_, record) = address_number_pair("MATROZOVA: BB,").expect("parse the record");
+        assert!(record.has_bez());
+        assert!(!record.contains(0, None));
+        assert!(!record.contains(10, None));
+
+        let (_, record) = address_number_pair("MALA: 2-10,1").expect("parse the record");
+        assert!(!record.has_bez());
+    }
+
+    #[test]
+    fn test_collapse_single_number_range() {
+        assert_eq!(broj("303-303"), Ok(("", Broj::from(BrojNumber::from(303)))));
+        assert_eq!(
+            broj("17A-17A,"),
+            Ok((",", Broj::from(BrojNumber::from((17, Some("A"))))))
+        );
+        assert_eq!(
+            broj("303-303A"),
+            Ok((
+                "",
+                Broj::from(BrojRange::from((
+                    BrojNumber::from(303),
+                    BrojNumber::from((303, Some("A")))
+                )))
+            ))
+        );
+    }
+
+    #[test]
+    fn test_range_parity() {
+        assert_eq!(BrojRange::from((2, 90)).parity(), Some(Parity::Even));
+        assert_eq!(BrojRange::from((1, 89)).parity(), Some(Parity::Odd));
+        assert_eq!(BrojRange::from((1, 10)).parity(), None);
+    }
+
+    #[test]
+    fn test_range_parity_annotation() {
+        let (rest, range) = address_number_range("2-90 (parne),1").expect("parse the even side");
+        assert_eq!(rest, ",1");
+        assert_eq!(range, BrojRange::from((2, 90)).with_parity(Parity::Even));
+        assert!(range.has_explicit_parity());
+
+        let (_, range) = address_number_range("1-89 (NEPARNE)").expect("parse the odd side");
+        assert_eq!(range.parity(), Some(Parity::Odd));
+        assert!(range.has_explicit_parity());
+
+        // the annotation overrides the inferred parity
+        let (_, range) = address_number_range("1-10 (parne)").expect("parse the range");
+        assert_eq!(range.parity(), Some(Parity::Even));
+        assert_eq!(
+            range.expand().map(|n| n.value).collect::<Vec<_>>(),
+            vec![1, 2, 4, 6, 8, 10]
+        );
+        assert_eq!(range.to_string(), "1-10 (parne)");
+
+        let (_, record) =
+            address_number_pair("MALA: 2-10 (parne),1-9 (neparne),  BRODSKA: 2").unwrap();
+        assert!(record.contains(4, None));
+        assert!(!record.contains(11, None));
+        assert_eq!(record.numbers().len(), 2);
+        assert_eq!(record.coverage(), Coverage::Both);
+    }
+
+    #[test]
+    fn test_coverage() {
+        let coverage = |input| address_number_pair(input).unwrap().1.coverage();
+        assert_eq!(
+            coverage("SEVASTOKRATORA BRANKA : 2-90,1-89,"),
+            Coverage::Both
+        );
+        assert_eq!(
+            coverage("SEVASTOKRATORA BRANKA : 2-90,"),
+            Coverage::EvenOnly
+        );
+        assert_eq!(coverage("STOJANA BOŠKOVIĆA : 1,5-17,"), Coverage::OddOnly);
+        assert_eq!(coverage("KULSKA: 23-30,"), Coverage::Both);
+        assert_eq!(coverage("MATROZOVA: BB,"), Coverage::Unknown);
+        assert_eq!(coverage("VOJVODE VRATKA : BB,4-28,"), Coverage::EvenOnly);
+    }
+
+    #[test]
+    fn test_dedup_bez() {
+        let (_, mut record) = address_number_pair("MALA: BB,BB,123,BB").expect("parse the record");
+        record.dedup_bez();
+        assert_eq!(
+            record,
+            AddressRecord::new(
+                "MALA",
+                vec![Broj::Bez, Broj::from(BrojNumber::from(123)), Broj::Bez]
+            )
+        );
+    }
+
+    #[test]
+    fn test_builder() {
+        let record = AddressRecordBuilder::default()
+            .street("MAJKE JUGOVIĆA")
+            .bez()
+            .number(30)
+            .number_ext(11, "E")
+            .range(16, 18)
+            .build();
+        assert_eq!(
+            record,
+            AddressRecord::new(
+                "MAJKE JUGOVIĆA",
+                vec![
+                    Broj::Bez,
+                    Broj::from(BrojNumber::from(30)),
+                    Broj::from(BrojNumber::from((11, Some("E")))),
+                    Broj::from(BrojRange::from((16, 18))),
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn test_street_name_with_numbers() {
+        let (_, record) = address_number_pair("KLISINA NOVA 10: 8-10").expect("parse the record");
+        assert_eq!(
+            record,
+            AddressRecord::new(
+                "KLISINA NOVA 10",
+                vec![Broj::from(BrojRange::from((8, 10)))]
+            )
+        );
+
+        let (_, record) =
+            address_number_pair("BATAJNIČKI DRUM 14 DEO: 14,").expect("parse the record");
+        assert_eq!(record.street(), "BATAJNIČKI DRUM 14 DEO");
+    }
+
+    #[test]
+    fn test_street_name_with_stray_colon() {
+        let (_, record) =
+            address_number_pair("DISKONT PKB: NOVA 21: 2-14,17,").expect("parse the record");
+        assert_eq!(record.street(), "DISKONT PKB: NOVA 21");
+
+        // the broken record is not glued to the following one
+        assert!(Addresses::parse("MALA: X1,  BRODSKA: 2-18,").is_err());
+    }
+
+    #[test]
+    fn test_normalizes_street_whitespace() {
+        let (_, record) =
+            address_number_pair("CARICE   JELENE \t: 2-26,").expect("parse the record");
+        assert_eq!(
+            record,
+            AddressRecord::new("CARICE JELENE", vec![Broj::from(BrojRange::from((2, 26)))])
+        );
+        assert_eq!(
+            normalize_whitespace(" CARICE\u{00A0}JELENE "),
+            "CARICE JELENE"
+        );
+        assert!(matches!(
+            normalize_whitespace("MALA"),
+            Cow::Borrowed("MALA")
+        ));
+    }
+
+    #[test]
+    fn test_record_separators() {
+        let single = Addresses::parse("MALA: 2-10,1, BRODSKA: 2-18, PILOTSKA: BB").unwrap();
+        let multi =
+            Addresses::parse("  MALA: 2-10,1,\t\n  BRODSKA: 2-18,     PILOTSKA: BB").unwrap();
+        let none = Addresses::parse("MALA: 2-10,1,BRODSKA: 2-18,PILOTSKA: BB").unwrap();
+        assert_eq!(single.items.len(), 3);
+        assert_eq!(single, multi);
+        assert_eq!(single, none);
+    }
+
+    #[test]
+    fn test_skips_records_without_street() {
+        let res = Addresses::parse("MALA: 1,   : 1-5,  BRODSKA: 2-18,").expect("parse the row");
+        assert_eq!(
+            res.items,
+            vec![
+                AddressRecord::new("MALA", vec![Broj::from(BrojNumber::from(1))]),
+                AddressRecord::new("BRODSKA", vec![Broj::from(BrojRange::from((2, 18)))]),
+            ]
+        );
+        assert_eq!(
+            res.warnings(),
+            &[ParseWarning::EmptyStreet(": 1-5,".into())]
+        );
+    }
+
+    #[test]
+    fn test_parse_reports_unparsed_tail() {
+        let res = Addresses::parse("MALA: 2-10,1,  ?? 17/B: #").expect("parse the row");
+        assert_eq!(res.items.len(), 1);
+        assert_eq!(
+            res.warnings(),
+            &[ParseWarning::UnparsedTail("?? 17/B: #".into())]
+        );
+        assert!(res.warnings()[0].to_string().contains("?? 17/B: #"));
+
+        let res = Addresses::parse("MALA: 2-10,1,  ").expect("parse the row");
+        assert!(res.warnings().is_empty());
+    }
+
+    #[test]
+    fn test_parse_lenient() {
+        let (res, errors) = Addresses::parse_lenient("MALA: 2-10,1,  BRODSKA: X1-,  PILOTSKA: BB");
+        assert_eq!(
+            res.items,
+            vec![
+                AddressRecord::new(
+                    "MALA",
+                    vec![
+                        Broj::from(BrojRange::from((2, 10))),
+                        Broj::from(BrojNumber::from(1))
+                    ]
+                ),
+                AddressRecord::new("PILOTSKA", vec![Broj::Bez]),
+            ]
+        );
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].chunk(), "BRODSKA: X1-,");
+
+        let strict = Addresses::parse("MALA: 2-10,1, BRODSKA: 2-18,").unwrap();
+        let (lenient, errors) = Addresses::parse_lenient("MALA: 2-10,1, BRODSKA: 2-18,");
+        assert_eq!(lenient, strict);
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn test_streets_without_colon() {
+        static INPUT: &str = "MALA: 2-10,1,  BRODSKA,  PILOTSKA: 2-18,  ROMSKA: 2,";
+        let expected = vec![
+            AddressRecord::new(
+                "MALA",
+                vec![
+                    Broj::from(BrojRange::from((2, 10))),
+                    Broj::from(BrojNumber::from(1)),
+                ],
+            ),
+            AddressRecord::new("BRODSKA", Vec::new()),
+            AddressRecord::new("PILOTSKA", vec![Broj::from(BrojRange::from((2, 18)))]),
+            AddressRecord::new("ROMSKA", vec![Broj::from(BrojNumber::from(2))]),
+        ];
+
+        let res = Addresses::parse(INPUT).expect("parse the row");
+        assert_eq!(res.items, expected);
+        assert_eq!(
+            res.warnings(),
+            &[ParseWarning::MissingColon("BRODSKA,  PILOTSKA".into())]
+        );
+
+        let (lenient, errors) = Addresses::parse_lenient(INPUT);
+        assert!(errors.is_empty());
+        assert_eq!(lenient.items, expected);
+    }
+
+    #[test]
+    fn test_validate_rejects_zero() {
+        let record = AddressRecord::new(
+            "MALA",
+            vec![
+                Broj::from(BrojNumber::from(2)),
+                Broj::from(BrojNumber::from(0)),
+            ],
+        );
+        assert_eq!(
+            record.validate(),
+            Err(ValidationError::ZeroNumber {
+                street: "MALA".to_owned()
+            })
+        );
+
+        let record = AddressRecord::new("MALA", vec![Broj::from(BrojRange::from((0, 10)))]);
+        assert!(record.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_rejects_large_numbers() {
+        let record = AddressRecord::new("MALA", vec![Broj::from(BrojNumber::from(10001))]);
+        assert!(record.validate().is_err());
+
+        let rules = ValidationRules {
+            max_number: 20000,
+            ..Default::default()
+        };
+        assert!(record.validate_with(&rules).is_ok());
+    }
+
+    #[test]
+    fn test_validate_flags_wide_ranges() {
+        let record = AddressRecordBuilder::default()
+            .street("MALA")
+            .range(1, 9999)
+            .build();
+        let error = record.validate().expect_err("the range is suspicious");
+        assert_eq!(
+            error,
+            ValidationError::RangeTooWide {
+                street: "MALA".to_owned(),
+                range: "1-9999".to_owned(),
+                max: 500,
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "MALA: range 1-9999 spans more than 500 numbers"
+        );
+
+        let rules = ValidationRules {
+            max_span: None,
+            ..Default::default()
+        };
+        assert!(record.validate_with(&rules).is_ok());
+
+        let record = AddressRecordBuilder::default()
+            .street("MALA")
+            .range(1, 89)
+            .build();
+        assert_eq!(record.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_validate_accepts_normal_record() {
+        let (_, record) = address_number_pair("MALA: BB,2-10,1,36A/1").unwrap();
+        assert_eq!(record.validate(), Ok(()));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let input = "AUTOPUT ZA NOVI SAD: BB,284,294-296F,  BATAJNIČKI DRUM: 36A/1,";
+        let parsed = Addresses::parse(input).expect("parse the address row");
+        let json = serde_json::to_string(&parsed).expect("serialize addresses");
+        let restored: Addresses<'_> = serde_json::from_str(&json).expect("deserialize addresses");
+        assert_eq!(restored, parsed);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_shape() {
+        let parsed =
+            Addresses::parse("MALA (DEO): BB,A,12B,2-10 (parne),120-,").expect("parse the row");
+        let json = serde_json::to_value(&parsed).expect("serialize addresses");
+        assert_eq!(
+            json,
+            serde_json::json!([{
+                "street": "MALA",
+                "note": "DEO",
+                "numbers": [
+                    "bb",
+                    {"letter": "A"},
+                    {"value": 12, "extension": "B"},
+                    {"from": {"value": 2}, "to": {"value": 10}, "explicit_parity": "Even"},
+                    {"from": {"value": 120}},
+                ],
+            }])
+        );
+        let text = json.to_string();
+        let restored: Addresses<'_> = serde_json::from_str(&text).expect("deserialize addresses");
+        assert_eq!(restored, parsed);
+
+        assert!(serde_json::from_str::<Broj<'_>>("\"BB\"").is_ok());
+        assert!(serde_json::from_str::<Broj<'_>>("\"cc\"").is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_full_row_round_trip() {
+        let parsed = Addresses::parse(TEST_INPUT).expect("parse the full row");
+        let json = serde_json::to_string(&parsed).expect("serialize addresses");
+        assert!(json.starts_with(
+            r#"[{"street":"AUTOPUT ZA NOVI SAD","numbers":["bb",{"value":284},{"from":{"value":294},"to":{"value":296,"extension":"F"}}]}"#
+        ));
+
+        let restored = {
+            let restored: Addresses<'_> =
+                serde_json::from_str(&json).expect("deserialize addresses");
+            restored.into_owned()
+        };
+        drop(json);
+        assert_eq!(restored, parsed);
+    }
+
+    static TEST_INPUT: &str = "AUTOPUT ZA NOVI SAD: BB,284,294-296F,  BATAJNIČKI DRUM: BB,261-265,269,283-293,299,303-303A,  BATAJNIČKI DRUM 14 DEO: 14,  NIKOLE SUKNJAREVIĆA PRIKE: 2-18,1-17, NASELJE BATAJNICA:   1 SREMSKOG ODREDA: 2-90,1-89,  AERODROMSKA: 68A-80,84-88I,98,1-1A,5-13,23A,  BANOVAČKA: 4A-12,20,24,28,34,1-1A,  BATAJNIČKIH ŽRTAVA: 2-16,1-13,  BATINSKE BITKE: 2-60,1-59,  BEČEJSKA: 22-26,30-32,42-44,  BIHAĆKA: 2-28,1-7,  BOJČINSKA: 1-15,  BOSANSKE KRAJINE: 2-86,1-73,  BRAĆE BARIŠIĆA: 2-18,1-3,7-19,  BRAĆE MIHAJLOVIĆ-TRIPIĆ: 6-106,43-45,49-51,  BRAĆE NEŠTINAC: 2-12,1-11,  BRAĆE RADIŠIĆ: 4A-4V,1-41,  BRAĆE SAVIĆA: 2-54,1-73,  BRAĆE SMILJANIĆA: 4-6,14-64,68-72,3-11,15-61A,65A-71,75B-83F/3,  BRAĆE VOJINOVIĆA: 1-1A,5-7A,11-11A,15-15A,19-19A,  BRANISLAVA BARIŠIĆA: 2-92,1-43,47,53-57,  BRILETOVA: 2-6,1-5,  BRODSKA: 2-18,1-19,  CARICE JELENE : 2-26,1-27,  DALMATINSKE ZAGORE : 8-16,20-160,1-145B,  DALMATINSKIH BRIGADA: 4A-6A,10-12,20,24,36A/1-56,60,19-21A,25-33,39-43,47,51,57-57,61-85,89-91,97-103,  DESPOTA IVANIŠA : 10-12,18-24,  DIMITRIJA LAZAROVA RAŠE: 2-28,1-33,37-41,  DISKONT PKB NOVA 21: 2-14,17,  ĐORĐA BOŠKOVIĆA - BATE: 6,12-16B,26-36A,42-54,3-19B,23-39,43-63B,  DRAGE MIHAJLOVIĆA: 2-58,1-47,51-53,  ĐURĐA BALŠIĆA : 2-6,10-20,3,9,  ISLAMA GRČKOG: 4,8,12-18,17,21-31,  IVANA DELNEGRA-ENGLEZA : 2-42,1-17,  IVANA SENKOVIĆA: 2-78,1-73,  JOVANA BRANKOVIĆA : 2-118,122-152,156-166C,170,174-176D,180-182,1-137,141-155,161-171,  KARLOVČIĆKA: 2-6,1-5,  KATICE OPAČIĆ: 2-18,22-40,44-46,50,72,76,94-94D,98-104D,1,5-11,17-17,25A-69B,  KESARA HRELJE : 2,12-24,28,34-40,  KESARA NOVAKA : 2-14A,  KESARA PRELJUBA : 4-8,12,20,24-26,30-36,3-9,13-25,  KESARA VOJIHNE : 4-6,3-23,27-33,  KLISINA NOVA  8: 2,3,7-17,  KLISINA NOVA  9: 2A,6,10,14,18-20,3-5,9-9A,13-17,  KNEZA PASKAČA : 2-14,18,1-5,  KRALJA MIHAILA ZETSKOG : 2-4,8-24,30-32,48-52,1-11,45-47,51-67O,73-83,87,  KRALJA RADOSLAVA : 38-120,126-148,152-178,53-81,85-85,99-99N,105-181,  KRALJA STEFANA TOMAŠA : 40-42,48-58,64-66,67-89,  KRALJA UROŠA PRVOG : 2-16G,1,9A,  KRALJA VLADISLAVA : 22-42,46-50B,54-102,106,110-116,120-150,13-29,33-35,39-43,47-61,65-73,77-117,121-129,133-139,  KULSKA: 23-29E,  MAJKE JUGOVIĆA: 16-16A,30-36,11-11E,99N,  MAJORA ZORANA RADOSAVLJEVIĆA : 2-50,116-226,236-258B,262-290,372-374,382,1-49,117-143,149-277,281,  MAKSIMA BRANKOVIĆA : 2-26,30,38-56,1-3,7-47,  MALA: 2-10,1,  MARKA PERIČINA-KAMENJARA : 2-8A,16,24-26,32,42-70,1,25,39-43,  MATROZOVA: BB,  MIHALJEVAČKA: 2-20,1-19,  MILICE RAKIĆ : 2-96,3-21,39-79,83-117,  MITROVAČKA: 2-26,1-27,  MRCINIŠTE NOVA 28: 2-10,14-16,24-36,3-27,  NATALIJE DUBAJIĆ: 2-6A,1-11,  NIKICE POPOVIĆA: 2-18,1-13,  NOVAKA ATANACKOVIĆA: 2-6,1-3,  NOVOSADSKA : 10-98,1-41,45-47,51-61D,65-75Ž,81D-81E,97G-99J,103A-109V,  OFICIRSKA KOLONIJA : 4-10,14-16,1-9,13-17,  PALIĆKA: 2-52,1-83,  PEĆINAČKA: 2-76,1-39,  PILOTSKA: 2-20,1-19,  PRIMOŠTENSKA: 3,11,19-21,  PUKOVNIKA MILENKA PAVLOVIĆA : 2-142,160-162,180,1-9A,13-127,143-159A,175,  RATARSKA: 2-42,1-39,  ROMSKA: 2,14-16,23,  SAVE GRKINIĆA: 2-30,1-33,43,  SAVE RADOVANOVIĆA: 2-2A,6-8A,12-12A,16,20-20A,1-5,15-17,  SEVASTOKRATORA BRANKA : 2-90,1-89,  SEVASTOKRATORA DEJANA : 2-36,40,1,9-43,47-49,  SEVASTOKRATORA VLATKA : 2-68,1-79,  ŠIMANOVAČKA: 2-80,1-55,  ŠIROKI PUT: 2-16A,36,1-19,31E-31K,  ŠKOLSKA: 2-6,1-5,  SLOBODANA MACURE : 2-4,8-12,1-15,33-37,41-69,  SREMSKOG FRONTA: 2-20,1-9,13-25,  STANKA TIŠME: 2-84G,31A-47,71B-85V,  STEVANA DUBAJIĆA : 2A-42,46-48,52-68,74-82,1-17,21-29,33-73,79-81,85-91,  STEVE STANKOVIĆA: 2-18,1-11,  STOJANA BOŠKOVIĆA : 1,5-17,  SUNCOKRETA: 2-6,10-14,20,24,28-30,  SVETISLAVA VULOVIĆA : 4,10,14-18,27-33,37,  SVETOG RAFAILA ŠIŠATOVAČKOG : 2-12,1-15,  SVETOG SERAFIMA SAROVSKOG : 2-12,1-15,  SVILAJSKA: 2-12,1-9,  TITELSKA: 10-12,18-20,  VASILIJA RANKOVIĆA-BAĆE: 2-12,1-5,9-19,  VERE MIŠČEVIĆ: 2-30,5-15,19-33,  VOJVOĐANSKIH BRIGADA: 2-34,44-134,1-37,41-87,91-139A,143-145Z,  VOJVODE JAKŠE : 2-10,  VOJVODE NOVAKA : 2-6,10,44-46E,1-29F,33-39V,55M-55N,61B,  VOJVODE VOJISLAVA VOJINOVIĆA : 2-16,1-9,  VOJVODE VRATKA : BB,4-28,1A-37,  ŽARKA BOKUNA: 2-104,108-110,1-11,49,61-103,121-129,  ŽARKA OBREŠKOG: 2-14,18-20,30,34-34,38-40,44,1A-29E,33-41B,  ŽIKE MARKOVIĆA: 2-10,1-13,  ŽUPANA PRIBILA : 2-36,1-31, NASELJE ZEMUN:   BATAJNIČKI DRUM 13 DEO: 301,  KLISINA NOVA 10: 8-10,  TEMERINSKA 1 DEO: 1,";
+
+    #[test]
+    fn test_full_row_test() {
+        let parsed = Addresses::parse(TEST_INPUT).expect("parse the full row");
+        assert_eq!(parsed.warnings(), &[]);
+        assert_eq!(parsed.items.len(), 107);
+
+        // the settlement headers are not glued to the streets after them
+        let streets = parsed.items.iter().map(AddressRecord::street).collect_vec();
+        assert!(streets.contains(&"1 SREMSKOG ODREDA"));
+        assert!(streets.contains(&"BATAJNIČKI DRUM 13 DEO"));
+        assert!(!streets.iter().any(|street| street.contains("NASELJE")));
+
+        // the streets are grouped under the settlements in the row order
+        let settlements = parsed
+            .items
+            .iter()
+            .map(AddressRecord::settlement)
+            .dedup_with_count()
+            .collect_vec();
+        assert_eq!(
+            settlements,
+            vec![(4, None), (100, Some("BATAJNICA")), (3, Some("ZEMUN"))]
+        );
+        assert_eq!(parsed.items[3].street(), "NIKOLE SUKNJAREVIĆA PRIKE");
+        assert_eq!(parsed.items[4].street(), "1 SREMSKOG ODREDA");
+        assert_eq!(
+            parsed.items[104..]
+                .iter()
+                .map(AddressRecord::to_string)
+                .collect_vec(),
+            vec![
+                "BATAJNIČKI DRUM 13 DEO: 301",
+                "KLISINA NOVA 10: 8-10",
+                "TEMERINSKA 1 DEO: 1"
+            ]
+        );
+
+        // `Ž` is an extension and does not break the NOVOSADSKA record
+        let novosadska = parsed
+            .items
+            .iter()
+            .find(|record| record.street() == "NOVOSADSKA")
+            .expect("the NOVOSADSKA record");
+        assert!(novosadska.contains(75, Some("Ž")));
+        assert!(novosadska.contains(104, None));
+
+        let (lenient, errors) = Addresses::parse_lenient(TEST_INPUT);
+        assert_eq!(errors, vec![]);
+        assert_eq!(lenient.items, parsed.items);
+    }
+
+    #[test]
+    fn test_get_by_street() {
+        let parsed = Addresses::parse(TEST_INPUT).expect("parse the full row");
+        let mala = parsed.get("MALA").expect("the MALA record");
+        assert_eq!(
+            mala.numbers(),
+            &[
+                Broj::Range(BrojRange::from((2, 10))),
+                Broj::Number(BrojNumber::from(1)),
+            ]
+        );
+        assert_eq!(
+            parsed.get(" KLISINA  NOVA 8").map(|r| r.street()),
+            Some("KLISINA NOVA 8")
+        );
+        assert_eq!(parsed.get("MAL"), None);
+        assert_eq!(parsed.get("NEPOSTOJEĆA"), None);
+
+        let parsed = Addresses::parse("MALA: 1,  BRODSKA: 2,  MALA: 3,").expect("parse the row");
+        assert_eq!(
+            parsed.get("MALA").map(AddressRecord::numbers),
+            Some(&[Broj::Number(BrojNumber::from(1))][..])
+        );
+        assert_eq!(parsed.get_all("MALA").count(), 2);
+    }
+
+    #[test]
+    fn test_sorted() {
+        let parsed = Addresses::parse("MALA: 2-10,1,BB,  BRODSKA: 20-").expect("parse the row");
+        let copy = parsed.clone();
+        let sorted = copy.sorted();
+
+        let mala = vec![
+            Broj::Bez,
+            Broj::Number(BrojNumber::from(1)),
+            Broj::Range(BrojRange::from((2, 10))),
+        ];
+        assert_eq!(
+            sorted.items,
+            vec![
+                AddressRecord::new("BRODSKA", vec![Broj::OpenRange(BrojNumber::from(20))]),
+                AddressRecord::new("MALA", mala),
+            ]
+        );
+        assert_eq!(copy, parsed);
+        assert_eq!(parsed.items[0].street(), "MALA");
+        assert_eq!(
+            parsed.items[0].numbers()[0],
+            Broj::Range(BrojRange::from((2, 10)))
+        );
+        assert_eq!(sorted.sorted(), sorted);
+    }
+
+    #[test]
+    fn test_street_note() {
+        let (_, record) = address_number_pair("NOVOSADSKA (deo): 1-5").expect("parse the record");
+        assert_eq!(record.street(), "NOVOSADSKA");
+        assert_eq!(record.note(), Some("deo"));
+        assert_eq!(record.numbers(), &[Broj::Range(BrojRange::from((1, 5)))]);
+
+        let (_, record) = address_number_pair("MALA: 1").expect("parse the record");
+        assert_eq!(record.note(), None);
+
+        for street in [
+            "MALA (deo (a))",
+            "MALA deo)",
+            "MALA (deo",
+            "(deo)",
+            "MALA ()",
+        ] {
+            let record = AddressRecord::new(street, Vec::new());
+            assert_eq!(record.street(), street);
+            assert_eq!(record.note(), None);
+        }
+
+        let parsed = Addresses::parse("MALA: 1,  BRODSKA (deo),  PILOTSKA (deo): 2-18")
+            .expect("parse the row");
+        assert_eq!(
+            parsed
+                .into_iter()
+                .map(|record| (record.street().to_owned(), record.note().map(str::to_owned)))
+                .collect_vec(),
+            vec![
+                ("MALA".to_owned(), None),
+                ("BRODSKA".to_owned(), Some("deo".to_owned())),
+                ("PILOTSKA".to_owned(), Some("deo".to_owned())),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_settlement_header() {
+        let parsed = Addresses::parse("MALA: 1,  NASELJE ZEMUN:   BRODSKA: 2-18,")
+            .expect("parse the row with a header");
+        assert_eq!(
+            parsed.items,
+            vec![
+                AddressRecordBuilder::default()
+                    .street("MALA")
+                    .number(1)
+                    .build(),
+                AddressRecordBuilder::default()
+                    .street("BRODSKA")
+                    .range(2, 18)
+                    .settlement("ZEMUN")
+                    .build(),
+            ]
+        );
+        assert_eq!(
+            parsed.to_string(),
+            "MALA: 1,  NASELJE ZEMUN:   BRODSKA: 2-18"
+        );
+
+        // a record without a settlement is not written under the header
+        // of the record before it
+        let mut reversed = parsed.clone();
+        reversed.items.reverse();
+        let text = reversed.to_string();
+        assert_eq!(text, "MALA: 1,  NASELJE ZEMUN:   BRODSKA: 2-18");
+        let reparsed = Addresses::parse(&text).expect("parse the written row");
+        assert_eq!(reparsed, parsed);
+        assert_eq!(
+            reparsed.get("MALA").map(AddressRecord::settlement),
+            Some(None)
+        );
+
+        // the header carries over to the chunks after it
+        let (lenient, errors) = Addresses::parse_lenient(
+            "NASELJE  NOVA GALENIKA:   MALA: 1,  BRODSKA: 2-18,  PILOTSKA: X-Y,  KULSKA: 3,",
+        );
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            lenient
+                .into_iter()
+                .map(|record| (
+                    record.street().to_owned(),
+                    record.settlement().map(str::to_owned)
+                ))
+                .collect_vec(),
+            ["MALA", "BRODSKA", "KULSKA"]
+                .map(|street| (street.to_owned(), Some("NOVA GALENIKA".to_owned())))
+        );
+
+        // followed by numbers it is a street
+        let parsed = Addresses::parse("NASELJE STARA KARABURMA: 2-10,").expect("parse the row");
+        assert_eq!(
+            parsed.items,
+            vec![AddressRecordBuilder::default()
+                .street("NASELJE STARA KARABURMA")
+                .range(2, 10)
+                .build()]
+        );
+    }
+
+    #[test]
+    fn test_street_starting_with_number() {
+        for row in [
+            "MALA: 2-10,1,  1 SREMSKOG ODREDA: 2-90,1-89,",
+            "MALA: 2-10,1, 1 SREMSKOG ODREDA: 2-90,1-89,",
+            "MALA: 2-10,1,1 SREMSKOG ODREDA: 2-90,1-89,",
+            "MALA: 2-10,1,1 SREMSKOG ODREDA: 2-90,1-89",
+        ] {
+            let parsed = Addresses::parse(row).expect("parse the row");
+            assert_eq!(
+                parsed.to_string(),
+                "MALA: 2-10,1,  1 SREMSKOG ODREDA: 2-90,1-89",
+                "{row}"
+            );
+            assert!(parsed.warnings().is_empty(), "{row}");
+            assert_eq!(Addresses::parse_lenient(row).0, parsed, "{row}");
+        }
+
+        let parsed = Addresses::parse("MALA: 2-10,13. OKTOBRA: 2-4,  22 OKTOBRA: 1,")
+            .expect("parse the row");
+        assert_eq!(
+            parsed.items.iter().map(AddressRecord::street).collect_vec(),
+            vec!["MALA", "13. OKTOBRA", "22 OKTOBRA"]
+        );
+        assert_eq!(parsed.get("MALA").map(|r| r.numbers().len()), Some(1));
+
+        // a number followed by a word which is not a street stays a number
+        let (_, numbers) = broj_list("2-10,1 NAPOMENA: RADOVI").expect("parse the list");
+        assert_eq!(numbers.len(), 2);
+    }
+
+    #[test]
+    fn test_many_streets_starting_with_number() {
+        // every comma is followed by a number which may start the next
+        // street, the row still parses in linear time
+        let row = std::iter::once("MALA: 1".to_owned())
+            .chain((1..=30).map(|i| format!("{i} MAJA: 2-10,{}", i + 2)))
+            .join(",");
+        let parsed = Addresses::parse(&row).expect("parse the row");
+        assert!(parsed.warnings().is_empty());
+        assert_eq!(parsed.items.len(), 31);
+        assert_eq!(
+            parsed.get("30 MAJA").map(AddressRecord::numbers),
+            Some(
+                &[
+                    Broj::Range(BrojRange::from((2, 10))),
+                    Broj::Number(BrojNumber::from(32)),
+                ][..]
+            )
+        );
+        assert_eq!(Addresses::parse_lenient(&row).0, parsed);
+    }
+
+    #[test]
+    fn test_street_part() {
+        for (street, base, part) in [
+            (
+                "BATAJNIČKI DRUM 14 DEO",
+                "BATAJNIČKI DRUM",
+                Some(Part::Deo(14)),
+            ),
+            ("TEMERINSKA 1 DEO", "TEMERINSKA", Some(Part::Deo(1))),
+            ("KLISINA NOVA  8", "KLISINA", Some(Part::Nova(8))),
+            ("DISKONT PKB NOVA 21", "DISKONT PKB", Some(Part::Nova(21))),
+            ("BATAJNIČKI DRUM", "BATAJNIČKI DRUM", None),
+            ("1 SREMSKOG ODREDA", "1 SREMSKOG ODREDA", None),
+            ("NOVA 21", "NOVA 21", None),
+            ("NOVA DEO", "NOVA DEO", None),
+            ("MALA NOVA", "MALA NOVA", None),
+        ] {
+            let record = AddressRecord::new(street, vec![]);
+            assert_eq!((record.base(), record.part()), (base, part), "{street}");
+        }
+        assert_eq!(Part::Deo(14).to_string(), "14 DEO");
+        assert_eq!(Part::Nova(8).to_string(), "NOVA 8");
+
+        let parsed = Addresses::parse(TEST_INPUT).expect("parse the full row");
+        let drum = parsed
+            .get_with_parts("BATAJNIČKI  DRUM")
+            .map(|record| (record.street(), record.part()))
+            .collect_vec();
+        assert_eq!(
+            drum,
+            vec![
+                ("BATAJNIČKI DRUM", None),
+                ("BATAJNIČKI DRUM 14 DEO", Some(Part::Deo(14))),
+                ("BATAJNIČKI DRUM 13 DEO", Some(Part::Deo(13))),
+            ]
+        );
+        assert_eq!(parsed.get_with_parts("KLISINA").count(), 3);
+    }
+
+    #[test]
+    fn test_trailing_note() {
+        for (row, note) in [
+            (
+                "MALA: 2-10,1,  NAPOMENA: RADOVI MOGU TRAJATI DUŽE.",
+                "NAPOMENA: RADOVI MOGU TRAJATI DUŽE.",
+            ),
+            ("MALA: 2-10,1,  NAPOMENA: 2 SATA", "NAPOMENA: 2 SATA"),
+            ("MALA: 2-10,1 NAPOMENA: 2 SATA", "NAPOMENA: 2 SATA"),
+        ] {
+            let parsed = Addresses::parse(row).expect("parse the row");
+            let mala = AddressRecordBuilder::default()
+                .street("MALA")
+                .range(2, 10)
+                .number(1)
+                .build();
+            assert_eq!(parsed.items, vec![mala], "{row}");
+            assert_eq!(
+                parsed.warnings(),
+                &[ParseWarning::UnparsedTail(note.into())]
+            );
+        }
+
+        // the records in the middle of the row do not need the comma
+        let parsed = Addresses::parse("MALA: 1 BRODSKA: 2 PILOTSKA: 3").expect("parse the row");
+        assert_eq!(parsed.street_count(), 3);
+    }
+
+    #[test]
+    fn test_into_owned_outlives_the_row() {
+        let owned = {
+            let row = String::from("MALA (DEO): BB,2-10,12A,C,  BRODSKA 2-18,  NAPOMENA");
+            let parsed = Addresses::parse(&row).expect("parse the row");
+            let expected = format!("{parsed:?}");
+            let owned = parsed.clone().into_owned();
+            assert_eq!(owned, parsed);
+            drop(parsed);
+            drop(row);
+            assert_eq!(format!("{owned:?}"), expected);
+            owned
+        };
+
+        // the owned addresses can be sent to another thread
+        let handle = std::thread::spawn(move || owned);
+        let owned = handle.join().expect("the thread returns the addresses");
+        let mala = owned.get("MALA").expect("the first record");
+        assert_eq!(mala.note(), Some("DEO"));
+        assert!(mala.contains(12, Some("a")));
+        assert!(!owned.warnings().is_empty());
+    }
+
+    #[test]
+    fn test_merge_duplicate_streets() {
+        let mut parsed = Addresses::parse(
+            "BATAJNIČKI DRUM: BB,261-265,  NASELJE BATAJNICA:   MALA: 2,  BATAJNIČKI  DRUM: 269,283-293,",
+        )
+        .expect("parse the row");
+        assert_eq!(parsed.get_all("BATAJNIČKI DRUM").count(), 2);
+
+        parsed.merge_duplicate_streets();
+        assert_eq!(parsed.street_count(), 2);
+        assert_eq!(
+            parsed.items,
+            vec![
+                AddressRecordBuilder::default()
+                    .street("BATAJNIČKI DRUM")
+                    .bez()
+                    .range(261, 265)
+                    .number(269)
+                    .range(283, 293)
+                    .build(),
+                AddressRecordBuilder::default()
+                    .street("MALA")
+                    .number(2)
+                    .settlement("BATAJNICA")
+                    .build(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_query() {
+        let parsed = Addresses::parse(
+            "MALA: 2-10,12,  MALA PIJACA: 12A,14,  MALI PUT: 12A,  BRODSKA: 1-9 (neparne),",
+        )
+        .expect("parse the row");
+
+        let streets = |query: &AddressQuery| {
+            parsed
+                .query(query)
+                .into_iter()
+                .map(AddressRecord::street)
+                .collect::<Vec<_>>()
+        };
+
+        let query = AddressQuery::default().street("mala");
+        assert_eq!(streets(&query), vec!["MALA", "MALA PIJACA"]);
+        let query = query.number(12);
+        assert_eq!(streets(&query), vec!["MALA"]);
+        let query = query.extension("a");
+        assert_eq!(streets(&query), vec!["MALA PIJACA"]);
+
+        assert_eq!(
+            streets(&AddressQuery::default().parity(Parity::Even)),
+            vec!["MALA", "MALA PIJACA", "MALI PUT"]
+        );
+        assert!(streets(&AddressQuery::default().number(4).parity(Parity::Odd)).is_empty());
+        assert_eq!(streets(&AddressQuery::default()).len(), 4);
+    }
+
+    #[test]
+    fn test_space_separated_numbers() {
+        let number = |value| Broj::from(BrojNumber::from(value));
+        let parsed = Addresses::parse("MALA: 2 18 1 17").expect("parse the row");
+        let records = parsed.into_iter().collect::<Vec<_>>();
+        assert_eq!(records.len(), 1);
+        assert_eq!(
+            records[0].numbers(),
+            &[number(2), number(18), number(1), number(17)]
+        );
+
+        let parsed = Addresses::parse("MALA: 2-18,1-17").expect("parse the row");
+        let records = parsed.into_iter().collect::<Vec<_>>();
+        assert_eq!(
+            records[0].numbers(),
+            &[
+                Broj::from(BrojRange::from((2, 18))),
+                Broj::from(BrojRange::from((1, 17)))
+            ]
+        );
+
+        // the extensions stay with their numbers and the record may go on
+        let parsed = Addresses::parse("MALA: BB 12A 14-20,  BRODSKA: 2,").expect("parse the row");
+        assert_eq!(
+            parsed.items,
+            vec![
+                AddressRecordBuilder::default()
+                    .street("MALA")
+                    .bez()
+                    .number_ext(12, "A")
+                    .range(14, 20)
+                    .build(),
+                AddressRecordBuilder::default()
+                    .street("BRODSKA")
+                    .number(2)
+                    .build(),
+            ]
+        );
+        assert!(parsed.warnings().is_empty());
+
+        // whitespace does not separate the numbers of a comma list
+        let (_, numbers) = broj_list("2 18,3").expect("parse the list");
+        assert_eq!(numbers, vec![number(2)]);
+    }
+
+    #[test]
+    fn test_street_count() {
+        let parsed = Addresses::parse(TEST_INPUT).expect("parse the full row");
+        // the settlement headers are not streets
+        assert_eq!(parsed.street_count(), 107);
+
+        let parsed = Addresses::parse("MALA: 1,  BRODSKA: 2,  MALA  : 3,").expect("parse the row");
+        assert_eq!(parsed.street_count(), 2);
+        assert_eq!(Addresses::parse_lenient("").0.street_count(), 0);
+    }
+
+    #[test]
+    fn test_full_row_round_trip() {
+        let parsed = Addresses::parse(TEST_INPUT).expect("parse the full row");
+        let text = parsed.to_string();
+        let reparsed = Addresses::parse(&text).expect("parse the written row");
+        assert_eq!(reparsed, parsed);
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        assert_eq!(
+            Broj::from(BrojNumber::from((36, Some("A/1")))).to_string(),
+            "36A/1"
+        );
+        assert_eq!(
+            Broj::from(BrojRange::from(((294, None), (296, Some("F"))))).to_string(),
+            "294-296F"
+        );
+        assert_eq!(Broj::Bez.to_string(), "BB");
+
+        let (_, record) = address_number_pair("NOVOSADSKA (deo): 1-5").expect("parse the record");
+        assert_eq!(record.to_string(), "NOVOSADSKA (deo): 1-5");
+
+        for row in [
+            "MALA: 2-10,1",
+            "BATAJNIČKI DRUM: BB,36A/1,303-303A,  MALA (DEO): A,B,120-",
+            "BRODSKA: 2-18 (parne),1-17 (neparne),  PILOTSKA: 2 4 6",
+            "ĐORĐA BOŠKOVIĆA - BATE: 6,12-16B,  KLISINA NOVA 8: 2,3,7-17",
+        ] {
+            let parsed = Addresses::parse(row).expect("parse the row");
+            let text = parsed.to_string();
+            let reparsed = Addresses::parse(&text).expect("parse the written row");
+            assert_eq!(reparsed, parsed, "{row} written as {text}");
+        }
     }
 }
@@ -0,0 +1,56 @@
+//! The health check of the day pages: each page has to be served with
+//! a successful status and contain the outages table.
+use std::fmt;
+
+use crate::pages::DayPage;
+use crate::scrape::{self, PageParser};
+
+/// The outcome of checking a day page, `failure` tells what is wrong with
+/// it.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct PageCheck {
+    pub url: String,
+    pub failure: Option<String>,
+}
+
+impl PageCheck {
+    pub fn passed(&self) -> bool {
+        self.failure.is_none()
+    }
+}
+
+impl fmt::Display for PageCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.failure {
+            None => write!(f, "PASS {}", self.url),
+            Some(failure) => write!(f, "FAIL {}: {failure}", self.url),
+        }
+    }
+}
+
+/// Fetches every day page and checks that it is served with a successful
+/// status and has the outages table. The rows are not parsed.
+pub async fn check_pages(
+    client: &reqwest::Client,
+    page_parser: &PageParser,
+    day_pages: &[DayPage],
+) -> Vec<PageCheck> {
+    let mut checks = Vec::with_capacity(day_pages.len());
+    for DayPage { url, .. } in day_pages {
+        let failure = match scrape::fetch_page(client, url).await {
+            Ok(page) if !(200..300).contains(&page.status) => {
+                Some(format!("status {}", page.status))
+            }
+            Ok(page) if !page_parser.has_data_table(&page.body) => {
+                Some("the page does not contain the data table".to_owned())
+            }
+            Ok(_) => None,
+            Err(e) => Some(format!("cannot fetch the page: {e}")),
+        };
+        checks.push(PageCheck {
+            url: url.clone(),
+            failure,
+        });
+    }
+    checks
+}
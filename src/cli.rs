@@ -0,0 +1,421 @@
+//! Command line options of the beobot binary.
+use chrono::NaiveTime;
+use clap::{Parser, Subcommand};
+
+use crate::clock::{Clock, SystemClock};
+use crate::config::Config;
+use crate::filter::{read_street_list, OutageFilter, Pagination};
+use crate::job::{ScrapeJob, ScrapeJobBuilder};
+use crate::output::OutputFormat;
+use crate::pages::{DAYS, DEFAULT_BASE_URL, MAX_DAYS};
+
+#[derive(Parser, Debug)]
+#[command(version, about = "Scheduled power outages in Beograd")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// How to print the outages.
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub output: OutputFormat,
+
+    /// The city to show the outages of, by its name in the configuration,
+    /// as beograd or novi-sad.
+    #[arg(long, default_value = "beograd")]
+    pub city: String,
+
+    /// Show a single day only, 0 is today.
+    #[arg(long, value_parser = clap::value_parser!(u8).range(..MAX_DAYS as i64))]
+    pub day: Option<u8>,
+
+    /// How many day pages to fetch, starting with today.
+    #[arg(
+        long,
+        default_value_t = DAYS as u8,
+        value_parser = clap::value_parser!(u8).range(1..=MAX_DAYS as i64)
+    )]
+    pub days_ahead: u8,
+
+    /// Consolidate the days into a single view with a row per street and
+    /// set of numbers. The calendar output is not affected.
+    #[arg(long)]
+    pub weekly: bool,
+
+    /// Print the urls of the day pages instead of fetching them.
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Parse the day pages saved to the files instead of fetching the
+    /// pages. The option may be repeated, a directory stands for its HTML
+    /// files. The pages are taken for the days from today on, in order,
+    /// and a page which cannot be parsed is skipped.
+    #[arg(long, conflicts_with = "day")]
+    pub file: Vec<std::path::PathBuf>,
+
+    /// The site to fetch the day pages from.
+    #[arg(long, default_value = DEFAULT_BASE_URL)]
+    pub base_url: String,
+
+    /// Read the day page urls of the cities and the selectors of the
+    /// outages table from the TOML file.
+    #[cfg(feature = "serde")]
+    #[arg(long)]
+    pub config: Option<std::path::PathBuf>,
+
+    /// Show only the outages of the streets whose name contains the text.
+    #[arg(long)]
+    pub street: Option<String>,
+
+    /// Show only the outages which list the house number.
+    #[arg(long)]
+    pub number: Option<usize>,
+
+    /// Show only the outages starting at or after the time, as HH:MM.
+    #[arg(long, alias = "since", value_parser = parse_clock_time)]
+    pub after: Option<NaiveTime>,
+
+    /// Show only the outages going on right now, Belgrade time, on
+    /// today's page.
+    #[arg(long, conflicts_with = "day")]
+    pub now: bool,
+
+    /// Show only the outages of the streets listed in the file, one name
+    /// per line. A street listed here is shown even if `--exclude-file`
+    /// lists it too.
+    #[arg(long)]
+    pub include_file: Option<std::path::PathBuf>,
+
+    /// Leave out the outages of the streets listed in the file, one name
+    /// per line.
+    #[arg(long)]
+    pub exclude_file: Option<std::path::PathBuf>,
+
+    /// Show at most this many rows over all the days, counted after the
+    /// filtering.
+    #[arg(long)]
+    pub limit: Option<usize>,
+
+    /// After the run, write the metrics of the day pages to the file in the
+    /// Prometheus text format.
+    #[arg(long)]
+    pub metrics_file: Option<std::path::PathBuf>,
+
+    /// Fail on the first malformed row instead of skipping it.
+    #[arg(long)]
+    pub strict: bool,
+
+    /// Log only the failures, whatever `RUST_LOG` says. The outages are
+    /// printed as usual.
+    #[arg(long)]
+    pub quiet: bool,
+
+    /// Leave out this many rows first, to page through the results with
+    /// `--limit`.
+    #[arg(long, default_value_t = 0)]
+    pub skip: usize,
+}
+
+#[derive(Subcommand, Eq, PartialEq, Clone, Copy, Debug)]
+pub enum Command {
+    /// Check that every day page is reachable and has the outages table,
+    /// print PASS or FAIL per page and fail if any page fails.
+    Check,
+}
+
+impl Cli {
+    /// The filter selecting the outages to show.
+    pub fn outage_filter(&self) -> anyhow::Result<OutageFilter> {
+        self.outage_filter_with(&SystemClock)
+    }
+
+    /// Same as [`Cli::outage_filter`] but `--now` is taken from the clock.
+    pub fn outage_filter_with(&self, clock: &impl Clock) -> anyhow::Result<OutageFilter> {
+        let mut filter = OutageFilter::new(self.street.as_deref(), self.number);
+        if let Some(after) = self.after {
+            filter = filter.after(after);
+        }
+        if self.now {
+            filter = filter.active_at(clock.now().time());
+        }
+        if let Some(path) = &self.include_file {
+            filter = filter.include_streets(read_street_list(path)?);
+        }
+        if let Some(path) = &self.exclude_file {
+            filter = filter.exclude_streets(read_street_list(path)?);
+        }
+        Ok(filter)
+    }
+
+    /// The configuration file if one is given, the built-in configuration
+    /// otherwise.
+    pub fn config(&self) -> anyhow::Result<Config> {
+        #[cfg(feature = "serde")]
+        if let Some(path) = &self.config {
+            return Config::load(path);
+        }
+        Ok(Config::default())
+    }
+
+    /// The scrape the options describe, with the configuration to use.
+    pub fn scrape_job(&self, config: Config) -> anyhow::Result<ScrapeJobBuilder> {
+        // the outages going on now are on today's page
+        let day = if self.now {
+            Some(0)
+        } else {
+            self.day.map(usize::from)
+        };
+        Ok(ScrapeJob::builder()
+            .city(&self.city)
+            .days(usize::from(self.days_ahead))
+            .day(day)
+            .base_url(&self.base_url)
+            .config(config)
+            .filter(self.outage_filter()?)
+            .strict(self.strict)
+            .files(self.file.clone()))
+    }
+
+    /// The window of the rows to show.
+    pub fn pagination(&self) -> Pagination {
+        Pagination::new(self.skip, self.limit)
+    }
+}
+
+fn parse_clock_time(input: &str) -> Result<NaiveTime, chrono::ParseError> {
+    NaiveTime::parse_from_str(input, "%H:%M")
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::scrape::Outage;
+    use std::process::ExitCode;
+
+    #[test]
+    fn test_default_output() {
+        let cli = Cli::try_parse_from(["beobot"]).expect("parse no arguments");
+        assert_eq!(cli.output, OutputFormat::Text);
+        assert_eq!(cli.city, "beograd");
+        assert_eq!(cli.day, None);
+        assert!(!cli.dry_run);
+
+        let cli = Cli::try_parse_from(["beobot", "--output", "table"]).expect("parse the output");
+        assert_eq!(cli.output, OutputFormat::Table);
+    }
+
+    #[test]
+    fn test_address_filter() {
+        let cli = Cli::try_parse_from([
+            "beobot",
+            "--output",
+            "ics",
+            "--street",
+            "Бродска",
+            "--number",
+            "12",
+        ])
+        .expect("parse the filter");
+        assert_eq!(cli.output, OutputFormat::Ics);
+        assert_eq!(cli.street.as_deref(), Some("Бродска"));
+        assert_eq!(cli.number, Some(12));
+    }
+
+    #[test]
+    fn test_city_and_day() {
+        let cli = Cli::try_parse_from(["beobot", "--dry-run", "--city", "novi-sad", "--day", "3"])
+            .expect("parse the city and the day");
+        assert!(cli.dry_run);
+        assert_eq!(cli.city, "novi-sad");
+        assert_eq!(cli.day, Some(3));
+
+        // the day has to be within the days ahead
+        let cli = Cli::try_parse_from(["beobot", "--day", "4"]).expect("parse the day");
+        assert!(cli
+            .scrape_job(Config::default())
+            .expect("the scrape job")
+            .day_pages()
+            .is_err());
+        assert!(Cli::try_parse_from(["beobot", "--day", "14"]).is_err());
+    }
+
+    #[test]
+    fn test_days_ahead() {
+        let cli = Cli::try_parse_from(["beobot"]).expect("parse no arguments");
+        assert_eq!(
+            cli.scrape_job(Config::default())
+                .expect("the scrape job")
+                .day_pages()
+                .expect("the day pages")
+                .len(),
+            DAYS
+        );
+
+        let cli = Cli::try_parse_from(["beobot", "--days-ahead", "2", "--base-url", "http://bg"])
+            .expect("parse the days ahead");
+        let urls = cli
+            .scrape_job(Config::default())
+            .expect("the scrape job")
+            .day_pages()
+            .expect("the day pages")
+            .into_iter()
+            .map(|page| page.url)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            urls,
+            vec![
+                "http://bg/Dan_0_Iskljucenja.htm",
+                "http://bg/Dan_1_Iskljucenja.htm"
+            ]
+        );
+
+        let cli = Cli::try_parse_from(["beobot", "--days-ahead", "6", "--day", "5"])
+            .expect("parse the days ahead");
+        assert_eq!(
+            cli.scrape_job(Config::default())
+                .expect("the scrape job")
+                .day_pages()
+                .expect("the day pages")[0]
+                .day,
+            5
+        );
+
+        assert!(Cli::try_parse_from(["beobot", "--days-ahead", "0"]).is_err());
+        assert!(Cli::try_parse_from(["beobot", "--days-ahead", "15"]).is_err());
+    }
+
+    #[test]
+    fn test_check_command() {
+        let cli = Cli::try_parse_from(["beobot"]).expect("parse no arguments");
+        assert_eq!(cli.command, None);
+
+        let cli = Cli::try_parse_from(["beobot", "--city", "novi-sad", "check"])
+            .expect("parse the check command");
+        assert_eq!(cli.command, Some(Command::Check));
+        assert_eq!(cli.city, "novi-sad");
+    }
+
+    #[test]
+    fn test_file() {
+        let cli = Cli::try_parse_from(["beobot", "--file", "page.htm"]).expect("parse the file");
+        assert_eq!(cli.file, vec![std::path::PathBuf::from("page.htm")]);
+
+        let cli = Cli::try_parse_from(["beobot", "--file", "a.htm", "--file", "pages"])
+            .expect("parse the files");
+        assert_eq!(cli.file.len(), 2);
+        assert!(Cli::try_parse_from(["beobot"])
+            .expect("parse no arguments")
+            .file
+            .is_empty());
+
+        assert!(Cli::try_parse_from(["beobot", "--file", "page.htm", "--day", "1"]).is_err());
+    }
+
+    #[test]
+    fn test_now() {
+        use crate::clock::{FixedClock, TIME_ZONE};
+        use crate::timeint::TimeRange;
+        use chrono::TimeZone;
+
+        let cli = Cli::try_parse_from(["beobot", "--now"]).expect("parse the now flag");
+        let day_pages = cli
+            .scrape_job(Config::default())
+            .expect("the scrape job")
+            .day_pages()
+            .expect("the day pages");
+        assert_eq!(day_pages.len(), 1);
+        assert_eq!(day_pages[0].day, 0);
+        assert!(Cli::try_parse_from(["beobot", "--now", "--day", "1"]).is_err());
+
+        let clock = FixedClock(
+            TIME_ZONE
+                .with_ymd_and_hms(2022, 6, 20, 23, 30, 0)
+                .single()
+                .expect("a valid local time"),
+        );
+        let filter = cli.outage_filter_with(&clock).expect("the filter");
+        let outages = ["08:30-14:00", "22:00-06:00", "23:00-23:45", "-"]
+            .into_iter()
+            .map(|time| Outage {
+                time: time.to_owned(),
+                time_ranges: TimeRange::parse_list(time).unwrap_or_default(),
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+        let active = outages
+            .iter()
+            .filter(|outage| filter.matches(outage))
+            .map(|outage| outage.time.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(active, vec!["22:00-06:00", "23:00-23:45"]);
+
+        let cli = Cli::try_parse_from(["beobot"]).expect("parse no arguments");
+        assert!(!cli
+            .outage_filter_with(&clock)
+            .expect("the filter")
+            .is_active());
+    }
+
+    #[test]
+    fn test_quiet() {
+        let cli = Cli::try_parse_from(["beobot"]).expect("parse no arguments");
+        assert!(!cli.quiet);
+
+        let cli = Cli::try_parse_from(["beobot", "--quiet", "--output", "table"])
+            .expect("parse the quiet flag");
+        assert!(cli.quiet);
+        assert_eq!(cli.output, OutputFormat::Table);
+    }
+
+    #[test]
+    fn test_after() {
+        let cli = Cli::try_parse_from(["beobot", "--after", "18:00"]).expect("parse the time");
+        assert_eq!(cli.after, NaiveTime::from_hms_opt(18, 0, 0));
+
+        let cli = Cli::try_parse_from(["beobot", "--since", "07:30"]).expect("parse the alias");
+        assert_eq!(cli.after, NaiveTime::from_hms_opt(7, 30, 0));
+
+        assert!(Cli::try_parse_from(["beobot", "--after", "25:00"]).is_err());
+    }
+
+    #[test]
+    fn test_exit_code() {
+        let outage = Outage {
+            streets: "MALA: 2-10,1,  BRODSKA: 2-18,".to_owned(),
+            ..Default::default()
+        };
+        let matched = |args: &[&str]| {
+            let cli = Cli::try_parse_from(args).expect("parse the filter");
+            let filter = cli.outage_filter().expect("the filter");
+            let matched = usize::from(filter.matches(&outage));
+            filter.exit_code(matched)
+        };
+
+        assert_eq!(
+            matched(&["beobot", "--street", "brodska", "--number", "4"]),
+            ExitCode::SUCCESS
+        );
+        assert_eq!(
+            matched(&["beobot", "--street", "brodska", "--number", "40"]),
+            ExitCode::FAILURE
+        );
+        assert_eq!(
+            matched(&["beobot", "--street", "pilotska"]),
+            ExitCode::FAILURE
+        );
+        assert_eq!(matched(&["beobot"]), ExitCode::SUCCESS);
+        assert_eq!(OutageFilter::default().exit_code(0), ExitCode::SUCCESS);
+    }
+
+    #[test]
+    fn test_limit_and_skip() {
+        let cli = Cli::try_parse_from(["beobot"]).expect("parse no arguments");
+        assert_eq!(cli.pagination(), Pagination::default());
+
+        let cli = Cli::try_parse_from(["beobot", "--limit", "5", "--skip", "10"])
+            .expect("parse the limit and the skip");
+        let mut pagination = cli.pagination();
+        let rows = pagination.page((1..=30).collect::<Vec<_>>());
+        assert_eq!(rows, (11..=15).collect::<Vec<_>>());
+    }
+}
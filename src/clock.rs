@@ -0,0 +1,32 @@
+//! The current time in the time zone the outages are published in.
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+
+/// The time zone of the times on the day pages.
+pub const TIME_ZONE: Tz = chrono_tz::Europe::Belgrade;
+
+/// Tells the current time, so the code asking for it can be run against
+/// a fixed time.
+pub trait Clock {
+    fn now(&self) -> DateTime<Tz>;
+}
+
+/// The clock of the system.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Tz> {
+        Utc::now().with_timezone(&TIME_ZONE)
+    }
+}
+
+/// A clock which always tells the same time.
+#[derive(Clone, Copy, Debug)]
+pub struct FixedClock(pub DateTime<Tz>);
+
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Tz> {
+        self.0
+    }
+}
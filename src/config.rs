@@ -0,0 +1,199 @@
+//! The cities and the page selectors, built in or read from a TOML file so
+//! the scraper can follow a changed or a new regional site.
+use std::collections::BTreeMap;
+
+use crate::pages::{City, DayPage};
+
+/// The placeholders of [`CityConfig::day_url`].
+const BASE_URL_PLACEHOLDER: &str = "{base_url}";
+const DAY_PLACEHOLDER: &str = "{day}";
+
+/// The cities by name, as given to `--city`, and the selectors of the
+/// outages table. A file overrides the built-in cities it names and the
+/// selectors it sets, the rest keep their defaults.
+#[derive(Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Config {
+    pub cities: BTreeMap<String, CityConfig>,
+    pub selectors: Selectors,
+}
+
+/// Where the day pages of a city are. The `{day}` placeholder of the url
+/// is replaced with the day number and `{base_url}` with the site.
+#[derive(Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
+pub struct CityConfig {
+    pub day_url: String,
+}
+
+/// The CSS selectors locating the outages table, its data rows and their
+/// cells.
+#[derive(Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Selectors {
+    pub table: String,
+    pub row: String,
+    pub cell: String,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        let cities = [City::Beograd, City::NoviSad]
+            .into_iter()
+            .map(|city| {
+                let day_url = format!(
+                    "{BASE_URL_PLACEHOLDER}/{}Dan_{DAY_PLACEHOLDER}_Iskljucenja.htm",
+                    city.page_prefix()
+                );
+                (city.name().to_owned(), CityConfig { day_url })
+            })
+            .collect();
+        Self {
+            cities,
+            selectors: Selectors::default(),
+        }
+    }
+}
+
+/// The default table selector matches the current layout of the page.
+impl Default for Selectors {
+    fn default() -> Self {
+        Self {
+            table: "table:nth-child(2)".to_owned(),
+            row: "tr:not(:first-child)".to_owned(),
+            cell: "td".to_owned(),
+        }
+    }
+}
+
+impl Config {
+    /// Reads the configuration out of a TOML document. The cities of the
+    /// document are added to the built-in ones, replacing those of the same
+    /// name.
+    #[cfg(feature = "serde")]
+    pub fn from_toml(text: &str) -> anyhow::Result<Self> {
+        let file: Config = toml::from_str(text)?;
+        let mut config = Config::default();
+        config.cities.extend(file.cities);
+        config.selectors = file.selectors;
+        Ok(config)
+    }
+
+    /// Reads the configuration file.
+    #[cfg(feature = "serde")]
+    pub fn load(path: &std::path::Path) -> anyhow::Result<Self> {
+        use anyhow::Context;
+
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("cannot read {}", path.display()))?;
+        Self::from_toml(&text).with_context(|| format!("cannot parse {}", path.display()))
+    }
+
+    /// Lists the first `days` day pages of the city, all of them or the
+    /// single `day` one, or returns `None` if the city is not configured.
+    pub fn day_pages(
+        &self,
+        base_url: &str,
+        city: &str,
+        days: usize,
+        day: Option<usize>,
+    ) -> Option<Vec<DayPage>> {
+        let city = self.cities.get(city)?;
+        let base_url = base_url.trim_end_matches('/');
+        let pages = (0..days)
+            .filter(|d| day.is_none_or(|day| day == *d))
+            .map(|day| DayPage {
+                day,
+                url: city
+                    .day_url
+                    .replace(BASE_URL_PLACEHOLDER, base_url)
+                    .replace(DAY_PLACEHOLDER, &day.to_string()),
+            })
+            .collect();
+        Some(pages)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::pages::{DAYS, DEFAULT_BASE_URL};
+
+    #[test]
+    fn test_default_day_pages() {
+        let config = Config::default();
+        let pages = config
+            .day_pages(DEFAULT_BASE_URL, "beograd", DAYS, None)
+            .expect("a built-in city");
+        assert_eq!(pages.len(), DAYS);
+        assert_eq!(
+            pages[0].url,
+            "https://elektrodistribucija.rs/Dan_0_Iskljucenja.htm"
+        );
+        assert_eq!(pages[3].day, 3);
+
+        assert_eq!(
+            config.day_pages("http://127.0.0.1:8080/", "novi-sad", DAYS, Some(2)),
+            Some(vec![DayPage {
+                day: 2,
+                url: "http://127.0.0.1:8080/NoviSad_Dan_2_Iskljucenja.htm".to_owned(),
+            }])
+        );
+        assert_eq!(config.day_pages(DEFAULT_BASE_URL, "nis", DAYS, None), None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_from_toml() {
+        let config = Config::from_toml(
+            r#"
+            [cities.nis]
+            day_url = "https://example.rs/nis/dan{day}.html"
+
+            [cities.beograd]
+            day_url = "{base_url}/bg/{day}.htm"
+
+            [selectors]
+            table = "table.outages"
+            row = "tbody tr"
+            "#,
+        )
+        .expect("parse the configuration");
+
+        assert_eq!(
+            config.day_pages(DEFAULT_BASE_URL, "nis", DAYS, Some(1)),
+            Some(vec![DayPage {
+                day: 1,
+                url: "https://example.rs/nis/dan1.html".to_owned(),
+            }])
+        );
+        assert_eq!(
+            config.day_pages("http://localhost/", "beograd", DAYS, Some(0)),
+            Some(vec![DayPage {
+                day: 0,
+                url: "http://localhost/bg/0.htm".to_owned(),
+            }])
+        );
+        assert_eq!(
+            config.cities.get("novi-sad"),
+            Config::default().cities.get("novi-sad")
+        );
+        assert_eq!(
+            config.selectors,
+            Selectors {
+                table: "table.outages".to_owned(),
+                row: "tbody tr".to_owned(),
+                cell: "td".to_owned(),
+            }
+        );
+
+        assert_eq!(
+            Config::from_toml("").expect("an empty file"),
+            Config::default()
+        );
+        assert!(Config::from_toml("[cities.nis]\nurl = 1").is_err());
+    }
+}
@@ -0,0 +1,354 @@
+//! Selection of the outages affecting a given address.
+use anyhow::Context;
+use chrono::NaiveTime;
+use itertools::Itertools;
+use std::path::Path;
+use std::process::ExitCode;
+
+use crate::scrape::Outage;
+use crate::script_mapper::{fold_diacritics, Mapper};
+
+/// Keeps the outages of the streets whose name contains the `street` text
+/// and, if the `number` is given, which list that house number.
+/// The street query is transliterated the same way as the scraped data
+/// and compared with the letters with diacritics folded, so `cike`
+/// matches `ČIKE MARKOVIĆA`.
+/// With [`OutageFilter::after`] only the outages starting at or after the
+/// time are kept and with [`OutageFilter::active_at`] those going on at
+/// the time.
+/// With [`OutageFilter::include_streets`] and
+/// [`OutageFilter::exclude_streets`] only the outages with a street passing
+/// the lists are kept. The listed names are normalized like the street
+/// query but have to match the whole street name. A street on both lists is
+/// kept: the include list takes precedence over the exclude one.
+#[derive(Eq, PartialEq, Clone, Default, Debug)]
+pub struct OutageFilter {
+    street: Option<String>,
+    number: Option<usize>,
+    after: Option<NaiveTime>,
+    active_at: Option<NaiveTime>,
+    include: Vec<String>,
+    exclude: Vec<String>,
+}
+
+impl OutageFilter {
+    pub fn new(street: Option<&str>, number: Option<usize>) -> Self {
+        let mapper = Mapper::new();
+        Self {
+            street: street.map(|street| fold_diacritics(&mapper.transoform(street.trim()))),
+            number,
+            after: None,
+            active_at: None,
+            include: Vec::new(),
+            exclude: Vec::new(),
+        }
+    }
+
+    /// Keeps only the outages starting at or after the time. An overnight
+    /// outage starts in the evening, so `22:00-06:00` is kept for `18:00`.
+    /// An outage with several windows is kept if any of them starts at or
+    /// after the time. The outages without a parsed time range are dropped.
+    pub fn after(mut self, time: NaiveTime) -> Self {
+        self.after = Some(time);
+        self
+    }
+
+    /// Keeps only the outages with a window holding the time, overnight
+    /// windows included. The outages without a parsed time range are
+    /// dropped.
+    pub fn active_at(mut self, time: NaiveTime) -> Self {
+        self.active_at = Some(time);
+        self
+    }
+
+    /// Keeps only the outages of the listed streets. An empty list keeps
+    /// every street.
+    pub fn include_streets<I, S>(mut self, streets: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.include = normalize_streets(streets);
+        self
+    }
+
+    /// Drops the outages of the listed streets, unless the street is on the
+    /// include list as well.
+    pub fn exclude_streets<I, S>(mut self, streets: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.exclude = normalize_streets(streets);
+        self
+    }
+
+    /// Tells whether the filter looks for an address, by street or number.
+    pub fn is_address_query(&self) -> bool {
+        self.street.is_some() || self.number.is_some()
+    }
+
+    /// The exit code for a run which found `matched` outages: looking for
+    /// an address it is a failure when none were found, so scripts can tell
+    /// whether the address is affected.
+    pub fn exit_code(&self, matched: usize) -> ExitCode {
+        if self.is_address_query() && matched == 0 {
+            ExitCode::FAILURE
+        } else {
+            ExitCode::SUCCESS
+        }
+    }
+
+    /// Tells whether any criteria is set.
+    pub fn is_active(&self) -> bool {
+        self.street.is_some()
+            || self.number.is_some()
+            || self.after.is_some()
+            || self.active_at.is_some()
+            || !self.include.is_empty()
+            || !self.exclude.is_empty()
+    }
+
+    /// Tells whether the street passes the include and the exclude lists.
+    fn street_listed(&self, street: &str) -> bool {
+        let street = fold_diacritics(street);
+        if self.include.contains(&street) {
+            return true;
+        }
+        self.include.is_empty() && !self.exclude.contains(&street)
+    }
+
+    pub fn matches(&self, outage: &Outage) -> bool {
+        if let Some(after) = self.after {
+            if !outage
+                .time_ranges
+                .iter()
+                .any(|range| range.start() >= after)
+            {
+                return false;
+            }
+        }
+
+        if let Some(time) = self.active_at {
+            if !outage.time_ranges.iter().any(|range| range.contains(time)) {
+                return false;
+            }
+        }
+
+        let has_lists = !self.include.is_empty() || !self.exclude.is_empty();
+        if !self.is_address_query() && !has_lists {
+            return true;
+        }
+
+        let addresses = match outage.addresses() {
+            Ok(addresses) => addresses,
+            Err(_) => return false,
+        };
+
+        addresses.into_iter().any(|record| {
+            if has_lists && !self.street_listed(record.street()) {
+                return false;
+            }
+            let street_matches = self
+                .street
+                .as_deref()
+                .is_none_or(|street| fold_diacritics(record.street()).contains(street));
+            let number_matches = self
+                .number
+                .is_none_or(|number| record.contains(number, None));
+            street_matches && number_matches
+        })
+    }
+}
+
+/// Reads a list of street names, one per line. The blank lines and the
+/// lines starting with `#` are skipped.
+pub fn read_street_list(path: &Path) -> anyhow::Result<Vec<String>> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("cannot read the street list {}", path.display()))?;
+    Ok(text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_owned)
+        .collect())
+}
+
+/// Transliterates the street names, folds their diacritics and collapses
+/// the whitespace, the way the scraped street names are compared.
+fn normalize_streets<I, S>(streets: I) -> Vec<String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let mapper = Mapper::new();
+    streets
+        .into_iter()
+        .map(|street| {
+            let street = street.as_ref().split_whitespace().join(" ");
+            fold_diacritics(&mapper.transoform(&street))
+        })
+        .collect()
+}
+
+/// Selects a window of the rows shown over the whole run: the first `skip`
+/// rows are left out and at most `limit` rows are kept after them. It is
+/// meant to be applied to the rows which already passed the filter.
+#[derive(Eq, PartialEq, Clone, Default, Debug)]
+pub struct Pagination {
+    skip: usize,
+    limit: Option<usize>,
+    seen: usize,
+}
+
+impl Pagination {
+    pub fn new(skip: usize, limit: Option<usize>) -> Self {
+        Self {
+            skip,
+            limit,
+            seen: 0,
+        }
+    }
+
+    /// Counts the next row and tells whether it is inside the window.
+    pub fn admit(&mut self) -> bool {
+        let index = self.seen;
+        self.seen += 1;
+        index >= self.skip && self.limit.is_none_or(|limit| index - self.skip < limit)
+    }
+
+    /// Keeps the rows which are inside the window.
+    pub fn page<T>(&mut self, rows: Vec<T>) -> Vec<T> {
+        rows.into_iter().filter(|_| self.admit()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::timeint::TimeRange;
+
+    fn outage(streets: &str) -> Outage {
+        Outage {
+            streets: streets.to_owned(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_filter_by_street_and_number() {
+        let outage = outage("MALA: 2-10,1,  BRODSKA: 2-18,");
+
+        assert!(OutageFilter::default().matches(&outage));
+        assert!(OutageFilter::new(Some("brodska"), None).matches(&outage));
+        assert!(OutageFilter::new(Some("Бродска"), Some(12)).matches(&outage));
+        assert!(!OutageFilter::new(Some("BRODSKA"), Some(20)).matches(&outage));
+        assert!(!OutageFilter::new(Some("PILOTSKA"), None).matches(&outage));
+        assert!(OutageFilter::new(None, Some(1)).matches(&outage));
+    }
+
+    #[test]
+    fn test_filter_folds_diacritics() {
+        let outage = outage("ČIKE MARKOVIĆA: 1-9,  ĐORĐA STANOJEVIĆA: 12,");
+
+        assert!(OutageFilter::new(Some("cike"), None).matches(&outage));
+        assert!(OutageFilter::new(Some("čike"), None).matches(&outage));
+        assert!(OutageFilter::new(Some("DJORDJA"), Some(12)).matches(&outage));
+        assert!(OutageFilter::new(Some("dorda"), None).matches(&outage));
+        assert!(!OutageFilter::new(Some("cika"), None).matches(&outage));
+        assert_eq!(
+            outage
+                .addresses()
+                .unwrap()
+                .into_iter()
+                .next()
+                .unwrap()
+                .street(),
+            "ČIKE MARKOVIĆA"
+        );
+    }
+
+    #[test]
+    fn test_filter_after() {
+        let timed = |time: &str| Outage {
+            time: time.to_owned(),
+            time_ranges: TimeRange::parse_list(time).unwrap_or_default(),
+            ..outage("MALA: 2-10,1,")
+        };
+        let filter = OutageFilter::default().after(NaiveTime::from_hms_opt(18, 0, 0).unwrap());
+
+        assert!(!filter.matches(&timed("08:00-10:00")));
+        assert!(filter.matches(&timed("22:00-06:00")));
+        assert!(filter.matches(&timed("18:00-20:00")));
+        assert!(filter.matches(&timed("08:00-10:00,19:00-21:00")));
+        assert!(!filter.matches(&timed("-")));
+        assert!(OutageFilter::default().matches(&timed("-")));
+    }
+
+    #[test]
+    fn test_filter_active_at() {
+        let timed = |time: &str| Outage {
+            time: time.to_owned(),
+            time_ranges: TimeRange::parse_list(time).unwrap_or_default(),
+            ..outage("MALA: 2-10,1,")
+        };
+        let filter = OutageFilter::default().active_at(NaiveTime::from_hms_opt(2, 0, 0).unwrap());
+
+        assert!(filter.is_active());
+        assert!(filter.matches(&timed("22:00-06:00")));
+        assert!(filter.matches(&timed("08:00-10:00,01:00-03:00")));
+        assert!(!filter.matches(&timed("08:00-10:00")));
+        assert!(!filter.matches(&timed("-")));
+    }
+
+    #[test]
+    fn test_filter_street_lists() {
+        let mala = outage("MALA: 2-10,1,");
+        let brodska = outage("BRODSKA: 2-18,");
+        let both = outage("MALA: 2-10,1,  BRODSKA: 2-18,");
+
+        let include = OutageFilter::default().include_streets(["Мала"]);
+        assert!(include.is_active());
+        assert!(include.matches(&mala));
+        assert!(!include.matches(&brodska));
+        assert!(include.matches(&both));
+
+        // the whole name has to match
+        assert!(!OutageFilter::default()
+            .include_streets(["MAL"])
+            .matches(&mala));
+
+        let exclude = OutageFilter::default().exclude_streets(["mala"]);
+        assert!(!exclude.matches(&mala));
+        assert!(exclude.matches(&brodska));
+        assert!(exclude.matches(&both));
+
+        // the include list takes precedence
+        let both_lists = OutageFilter::default()
+            .include_streets(["mala"])
+            .exclude_streets(["mala", "brodska"]);
+        assert!(both_lists.matches(&mala));
+        assert!(!both_lists.matches(&brodska));
+
+        // the lists narrow the address query down
+        let query = OutageFilter::new(None, Some(12)).exclude_streets(["brodska"]);
+        assert!(!query.matches(&both));
+    }
+
+    #[test]
+    fn test_pagination() {
+        let mut pagination = Pagination::new(10, Some(5));
+        // the rows come in pages, the window spans them
+        let first = pagination.page((1..=12).collect());
+        let second = pagination.page((13..=30).collect());
+        assert_eq!(first, vec![11, 12]);
+        assert_eq!(second, vec![13, 14, 15]);
+
+        let mut pagination = Pagination::default();
+        assert_eq!(pagination.page(vec![1, 2, 3]), vec![1, 2, 3]);
+
+        let mut pagination = Pagination::new(0, Some(0));
+        assert!(!pagination.admit());
+    }
+}
@@ -0,0 +1,324 @@
+//! A whole scrape, from the day pages of a city to the outages passing the
+//! filter, for running it from code the way the binary does.
+use anyhow::{anyhow, bail, Context, Result as AnyhowResult};
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tokio_util::sync::CancellationToken;
+use tracing::Instrument;
+
+use crate::config::Config;
+use crate::filter::OutageFilter;
+use crate::pages::{DayPage, DAYS, DEFAULT_BASE_URL};
+use crate::scrape::{self, ClientConfig, Outage, PageParser, PageStats, ParsedPage};
+use crate::source::{HttpSource, PageSource};
+
+/// Receives the pages of a [`ScrapeJob`] as they are processed, e.g. to
+/// print the outages as soon as their page is parsed.
+pub trait PageSink {
+    /// Called with every processed page, its outages already filtered.
+    fn page(&mut self, day: usize, page: &ParsedPage) -> AnyhowResult<()>;
+
+    /// Called once all the pages are processed, or the job is cancelled.
+    fn finish(&mut self) -> AnyhowResult<()> {
+        Ok(())
+    }
+}
+
+impl<F> PageSink for F
+where
+    F: FnMut(usize, &ParsedPage) -> AnyhowResult<()>,
+{
+    fn page(&mut self, day: usize, page: &ParsedPage) -> AnyhowResult<()> {
+        self(day, page)
+    }
+}
+
+/// Fetches the day pages from the source, parses them and keeps the
+/// outages passing the filter.
+///
+/// ```no_run
+/// use beobot::job::ScrapeJob;
+///
+/// # async fn example() -> anyhow::Result<()> {
+/// let job = ScrapeJob::builder()
+///     .city("novi-sad")
+///     .days(2)
+///     .client(reqwest::Client::new())
+///     .build()?;
+/// let outages = job.run().await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct ScrapeJob<S> {
+    source: S,
+    day_pages: Vec<DayPage>,
+    files: Vec<PathBuf>,
+    page_parser: PageParser,
+    filter: OutageFilter,
+    strict: bool,
+    cancel: CancellationToken,
+    output: Option<Mutex<Box<dyn PageSink + Send>>>,
+}
+
+impl ScrapeJob<()> {
+    pub fn builder() -> ScrapeJobBuilder {
+        ScrapeJobBuilder::default()
+    }
+}
+
+impl<S: PageSource> ScrapeJob<S> {
+    /// The day pages the job fetches.
+    pub fn day_pages(&self) -> &[DayPage] {
+        &self.day_pages
+    }
+
+    pub fn page_parser(&self) -> &PageParser {
+        &self.page_parser
+    }
+
+    pub fn filter(&self) -> &OutageFilter {
+        &self.filter
+    }
+
+    /// Processes the pages in order and returns the outages passing the
+    /// filter, every page is handed over to the output as well.
+    ///
+    /// The day pages are fetched from the source: a page which is not
+    /// available is skipped, one which cannot be fetched or parsed fails
+    /// the job. Once the job is cancelled no more pages are fetched and the
+    /// outages found by then are returned. The saved pages, if any are
+    /// given, are read instead: a file which cannot be read or parsed is
+    /// skipped, in the strict mode the job fails after the batch.
+    pub async fn run(&self) -> AnyhowResult<Vec<Outage>> {
+        let mut outages = Vec::new();
+        if self.files.is_empty() {
+            self.run_pages(&mut outages).await?;
+        } else {
+            self.run_files(&mut outages)?;
+        }
+
+        if let Some(output) = &self.output {
+            output.lock().expect("not poisoned").finish()?;
+        }
+        Ok(outages)
+    }
+
+    async fn run_pages(&self, outages: &mut Vec<Outage>) -> AnyhowResult<()> {
+        for DayPage { day, url } in self.day_pages.iter() {
+            let span = scrape::page_span(url, *day);
+            let body = tokio::select! {
+                biased;
+                _ = self.cancel.cancelled() => {
+                    tracing::info!(parent: &span, "cancelled, skipping the remaining pages");
+                    break;
+                }
+                body = self.source.fetch(url).instrument(span.clone()) => body?,
+            };
+            let Some(body) = body else {
+                continue;
+            };
+            let stats = span
+                .in_scope(|| self.process_page(*day, &body, outages))
+                .with_context(|| scrape::page_context(*day, url))?;
+            tracing::info!(parent: &span, "day {day}: {stats}");
+        }
+        Ok(())
+    }
+
+    fn run_files(&self, outages: &mut Vec<Outage>) -> AnyhowResult<()> {
+        let files = scrape::page_files(&self.files)?;
+        let failed =
+            scrape::scrape_files_with(&files, |day, body| self.process_page(day, body, outages));
+        if failed > 0 && self.strict {
+            bail!("{failed} of {} files failed", files.len());
+        } else if failed > 0 {
+            tracing::warn!("{failed} of {} files skipped", files.len());
+        }
+        Ok(())
+    }
+
+    /// Parses the page, filters its outages and hands the page over to the
+    /// output.
+    fn process_page(
+        &self,
+        day: usize,
+        body: &str,
+        outages: &mut Vec<Outage>,
+    ) -> AnyhowResult<PageStats> {
+        let mut page = self.page_parser.parse_page(body)?;
+        page.outages.retain(|outage| self.filter.matches(outage));
+        if let Some(output) = &self.output {
+            output.lock().expect("not poisoned").page(day, &page)?;
+        }
+        outages.append(&mut page.outages);
+        Ok(page.stats)
+    }
+}
+
+/// Configures a [`ScrapeJob`]: the city by its configured name, how many
+/// days ahead or a single day, the site, the configuration and the filter,
+/// along with the HTTP client, the output and the cancellation of the run.
+pub struct ScrapeJobBuilder {
+    city: String,
+    days: usize,
+    day: Option<usize>,
+    base_url: String,
+    config: Config,
+    filter: OutageFilter,
+    strict: bool,
+    client: Option<reqwest::Client>,
+    retries: u32,
+    files: Vec<PathBuf>,
+    cancel: CancellationToken,
+    output: Option<Box<dyn PageSink + Send>>,
+}
+
+impl Default for ScrapeJobBuilder {
+    fn default() -> Self {
+        Self {
+            city: "beograd".to_owned(),
+            days: DAYS,
+            day: None,
+            base_url: DEFAULT_BASE_URL.to_owned(),
+            config: Config::default(),
+            filter: OutageFilter::default(),
+            strict: false,
+            client: None,
+            retries: ClientConfig::default().retries,
+            files: Vec::new(),
+            cancel: CancellationToken::new(),
+            output: None,
+        }
+    }
+}
+
+impl fmt::Debug for ScrapeJobBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ScrapeJobBuilder")
+            .field("city", &self.city)
+            .field("days", &self.days)
+            .field("day", &self.day)
+            .field("base_url", &self.base_url)
+            .field("config", &self.config)
+            .field("filter", &self.filter)
+            .field("strict", &self.strict)
+            .field("retries", &self.retries)
+            .field("files", &self.files)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ScrapeJobBuilder {
+    pub fn city(mut self, city: &str) -> Self {
+        self.city = city.to_owned();
+        self
+    }
+
+    pub fn days(mut self, days: usize) -> Self {
+        self.days = days;
+        self
+    }
+
+    pub fn day(mut self, day: Option<usize>) -> Self {
+        self.day = day;
+        self
+    }
+
+    pub fn base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.to_owned();
+        self
+    }
+
+    pub fn config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    pub fn filter(mut self, filter: OutageFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// See [`PageParser::strict`]. A batch of saved pages with a failed
+    /// file fails the job as well.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// The client [`ScrapeJobBuilder::build`] fetches the pages with, one
+    /// with the default [`ClientConfig`] is built otherwise.
+    pub fn client(mut self, client: reqwest::Client) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// How many times a request which failed to complete is repeated.
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Reads the day pages saved to the files instead of fetching them,
+    /// see [`scrape::page_files`].
+    pub fn files(mut self, files: Vec<PathBuf>) -> Self {
+        self.files = files;
+        self
+    }
+
+    /// Stops the fetching once the token is cancelled, e.g. on Ctrl-C.
+    pub fn cancellation(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// Hands every processed page over to the sink.
+    pub fn output(mut self, output: impl PageSink + Send + 'static) -> Self {
+        self.output = Some(Box::new(output));
+        self
+    }
+
+    /// The configured name of the city the job scrapes.
+    pub fn city_name(&self) -> &str {
+        &self.city
+    }
+
+    /// Lists the day pages of the job. The day has to be within the days
+    /// ahead and the city has to be configured.
+    pub fn day_pages(&self) -> AnyhowResult<Vec<DayPage>> {
+        let days = self.days;
+        if let Some(day) = self.day.filter(|day| *day >= days) {
+            bail!("day {day} is not within the {days} days ahead");
+        }
+        self.config
+            .day_pages(&self.base_url, &self.city, days, self.day)
+            .ok_or_else(|| anyhow!("the city {} is not configured", self.city))
+    }
+
+    /// Builds the job fetching the pages over HTTP.
+    pub fn build(mut self) -> AnyhowResult<ScrapeJob<HttpSource>> {
+        let client = match self.client.take() {
+            Some(client) => client,
+            None => scrape::build_client(&ClientConfig::default())?,
+        };
+        let source = HttpSource::new(client, self.retries);
+        self.build_with(source)
+    }
+
+    /// Builds the job reading the pages from the source.
+    pub fn build_with<S: PageSource>(self, source: S) -> AnyhowResult<ScrapeJob<S>> {
+        let day_pages = self.day_pages()?;
+        let page_parser = PageParser::with_selectors(&self.config.selectors)?.strict(self.strict);
+        Ok(ScrapeJob {
+            source,
+            day_pages,
+            files: self.files,
+            page_parser,
+            filter: self.filter,
+            strict: self.strict,
+            cancel: self.cancel,
+            output: self.output.map(Mutex::new),
+        })
+    }
+}
@@ -0,0 +1,18 @@
+//! Beobot scrapes the scheduled power outages published by
+//! Elektrodistribucija Srbije and parses the affected addresses.
+pub mod addresses;
+pub mod check;
+pub mod cli;
+pub mod clock;
+pub mod config;
+pub mod filter;
+pub mod job;
+pub mod logging;
+pub mod metrics;
+pub mod output;
+pub mod pages;
+pub mod scrape;
+pub mod script_mapper;
+pub mod source;
+pub mod timeint;
+pub mod weekly;
@@ -0,0 +1,66 @@
+//! Logging setup shared by the binary and the tests.
+use tracing_subscriber::EnvFilter;
+
+/// The filter used when `RUST_LOG` is not set.
+pub const DEFAULT_FILTER: &str = "info";
+
+/// The filter used with `--quiet`, only the failures are logged.
+pub const QUIET_FILTER: &str = "error";
+
+/// Builds the log filter out of the `RUST_LOG` value, falling back to
+/// [`DEFAULT_FILTER`] when it is absent or cannot be parsed.
+pub fn env_filter(rust_log: Option<&str>) -> EnvFilter {
+    rust_log
+        .and_then(|directives| EnvFilter::try_new(directives).ok())
+        .unwrap_or_else(|| EnvFilter::new(DEFAULT_FILTER))
+}
+
+/// Builds the log filter of a run, `quiet` overrides `RUST_LOG` with
+/// [`QUIET_FILTER`].
+pub fn log_filter(rust_log: Option<&str>, quiet: bool) -> EnvFilter {
+    if quiet {
+        EnvFilter::new(QUIET_FILTER)
+    } else {
+        env_filter(rust_log)
+    }
+}
+
+/// Installs the global subscriber driven by `RUST_LOG`, or logging only
+/// the errors if `quiet` is set.
+///
+/// Installing it a second time (e.g. from several tests) is not an error,
+/// the first subscriber stays in place.
+pub fn init(quiet: bool) {
+    let rust_log = std::env::var(EnvFilter::DEFAULT_ENV).ok();
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(log_filter(rust_log.as_deref(), quiet))
+        .try_init();
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_default_filter_is_info() {
+        assert_eq!(env_filter(None).to_string(), "info");
+    }
+
+    #[test]
+    fn test_filter_honors_rust_log() {
+        assert_eq!(env_filter(Some("debug")).to_string(), "debug");
+    }
+
+    #[test]
+    fn test_quiet_overrides_rust_log() {
+        assert_eq!(log_filter(Some("debug"), true).to_string(), "error");
+        assert_eq!(log_filter(Some("debug"), false).to_string(), "debug");
+    }
+
+    #[test]
+    fn test_init_twice() {
+        init(false);
+        init(true);
+    }
+}
@@ -1,75 +1,243 @@
-use anyhow::{anyhow, bail, Result as AnyhowResult};
-use itertools::Itertools;
-use scraper::{Html, Selector};
-use tracing::Level;
-use tracing_subscriber::FmtSubscriber;
+use anyhow::Result as AnyhowResult;
+use chrono::{Local, NaiveDate, TimeDelta};
+use clap::Parser;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use tokio_util::sync::CancellationToken;
+
+use beobot::check;
+use beobot::cli::{Cli, Command};
+use beobot::filter::Pagination;
+use beobot::job::PageSink;
+use beobot::logging;
+use beobot::metrics::{self, DayMetrics};
+#[cfg(feature = "serde")]
+use beobot::output::RowWarning;
+use beobot::output::{self, OutputFormat};
+use beobot::pages::DayPage;
+use beobot::scrape::{self, ClientConfig, Outage, ParsedPage};
+use beobot::weekly::aggregate;
 
-mod addresses;
-mod script_mapper;
-mod timeint;
-
-use addresses::Addresses;
-use script_mapper::Mapper;
+#[tokio::main]
+async fn main() -> AnyhowResult<ExitCode> {
+    let cli = Cli::parse();
+    logging::init(cli.quiet);
+
+    let builder = cli.scrape_job(cli.config()?)?;
+    let day_pages = builder.day_pages()?;
+    if cli.dry_run {
+        output::write_urls(&mut std::io::stdout().lock(), &day_pages)?;
+        return Ok(ExitCode::SUCCESS);
+    }
 
-// https://elektrodistribucija.rs/NoviSad_Dan_0_Iskljucenja.htm
+    let client_config = ClientConfig::default();
+    let client = scrape::build_client(&client_config)?;
 
-static BEOGRAD_DAY_0: &str = "https://elektrodistribucija.rs/Dan_0_Iskljucenja.htm";
+    // Ctrl-C stops the fetching, the pages parsed so far are still printed
+    let cancel = CancellationToken::new();
+    tokio::spawn({
+        let cancel = cancel.clone();
+        async move {
+            if tokio::signal::ctrl_c().await.is_ok() {
+                cancel.cancel();
+            }
+        }
+    });
+
+    let printer = Printer::new(&cli, builder.city_name(), &day_pages);
+    let job = builder
+        .client(client.clone())
+        .retries(client_config.retries)
+        .cancellation(cancel)
+        .output(printer)
+        .build()?;
+
+    if cli.command == Some(Command::Check) {
+        let checks = check::check_pages(&client, job.page_parser(), job.day_pages()).await;
+        for page_check in checks.iter() {
+            println!("{page_check}");
+        }
+        return Ok(if checks.iter().all(|c| c.passed()) {
+            ExitCode::SUCCESS
+        } else {
+            ExitCode::FAILURE
+        });
+    }
 
-static BEOGRAD_DAY_1: &str = "https://elektrodistribucija.rs/Dan_1_Iskljucenja.htm";
+    let outages = job.run().await?;
+    Ok(job.filter().exit_code(outages.len()))
+}
 
-static BEOGRAD_DAY_2: &str = "https://elektrodistribucija.rs/Dan_2_Iskljucenja.htm";
+/// Prints the outages of the pages in the chosen format. The formats
+/// printing a page at a time do it as soon as the page is parsed, the
+/// calendar, the JSON document and the weekly view are rendered once all
+/// the pages are processed, along with the metrics file.
+struct Printer {
+    output: OutputFormat,
+    weekly: bool,
+    pagination: Pagination,
+    city: String,
+    metrics_file: Option<PathBuf>,
+    day_metrics: Vec<DayMetrics>,
+    /// The outages of the formats rendered at the end, with their date.
+    collected: Vec<(NaiveDate, Outage)>,
+    by_day: Vec<(usize, Outage)>,
+    #[cfg(feature = "serde")]
+    warnings: Vec<RowWarning>,
+}
 
-static BEOGRAD_DAY_3: &str = "https://elektrodistribucija.rs/Dan_3_Iskljucenja.htm";
+impl Printer {
+    fn new(cli: &Cli, city: &str, day_pages: &[DayPage]) -> Self {
+        Self {
+            output: cli.output,
+            weekly: cli.weekly && cli.output != OutputFormat::Ics,
+            pagination: cli.pagination(),
+            city: city.to_owned(),
+            metrics_file: cli.metrics_file.clone(),
+            day_metrics: day_pages
+                .iter()
+                .map(|page| DayMetrics::new(page.day))
+                .collect(),
+            collected: Vec::new(),
+            by_day: Vec::new(),
+            #[cfg(feature = "serde")]
+            warnings: Vec::new(),
+        }
+    }
 
-static BEOGRAD: &[&str] = &[BEOGRAD_DAY_0, BEOGRAD_DAY_1, BEOGRAD_DAY_2, BEOGRAD_DAY_3];
+    /// Prints the outages of the page which fall into the window of the
+    /// pagination, or keeps them for the formats rendered at the end.
+    fn print_page(&mut self, day: usize, page: &ParsedPage) -> AnyhowResult<()> {
+        let outages = self.pagination.page(page.outages.clone());
+        // the day pages are numbered from today on
+        let date = page
+            .date
+            .unwrap_or_else(|| Local::now().date_naive() + TimeDelta::days(day as i64));
+
+        match self.output {
+            OutputFormat::Text => {
+                for outage in outages.iter() {
+                    println!("{}\t{}", outage.municipality, outage.windows());
+                    match outage.addresses() {
+                        Ok(addresses) => print!("{}", output::render_addresses_text(&addresses)),
+                        // the streets are shown as they are published
+                        Err(e) => {
+                            tracing::warn!("cannot parse the streets of day {day}: {e}");
+                            println!("    {}", outage.streets);
+                        }
+                    }
+                }
+            }
+            OutputFormat::Table => println!("{}", output::render_table(&outages)),
+            OutputFormat::Markdown => print!("{}", output::render_markdown(&outages)),
+            OutputFormat::Ics => {
+                self.collected
+                    .extend(outages.into_iter().map(|outage| (date, outage)));
+            }
+            #[cfg(feature = "serde")]
+            OutputFormat::Jsonl => {
+                for outage in outages.iter() {
+                    output::write_jsonl(&mut std::io::stdout().lock(), outage)?;
+                }
+            }
+            #[cfg(feature = "serde")]
+            OutputFormat::Json | OutputFormat::JsonByStreet => {
+                self.collected
+                    .extend(outages.into_iter().map(|outage| (date, outage)));
+            }
+        }
+        Ok(())
+    }
 
-#[tokio::main]
-async fn main() -> AnyhowResult<()> {
-    let subscriber = FmtSubscriber::builder()
-        .with_max_level(Level::TRACE)
-        .finish();
-    tracing::subscriber::set_global_default(subscriber).expect("setting default subscriber failed");
-
-    let text_mapper = Mapper::new();
-
-    let table_selector = Selector::parse("table:nth-child(2)").map_err(|e| anyhow!("{e:?}"))?;
-    let tr_selector: Selector =
-        Selector::parse("tr:not(:first-child)").map_err(|e| anyhow!("{e:?}"))?;
-    let td_selector = Selector::parse("td").map_err(|e| anyhow!("{e:?}"))?;
-
-    for url in BEOGRAD.iter() {
-        let body = reqwest::get(*url).await?.text().await?;
-
-        let document = Html::parse_document(&body);
-
-        if let Some(data_table) = document.select(&table_selector).next() {
-            for (i, row) in data_table.select(&tr_selector).enumerate() {
-                let mut data_sel = row.select(&td_selector);
-                let columns = data_sel.next().and_then(|d| {
-                    data_sel.next().and_then(|t| {
-                        data_sel.next().map(|s| {
-                            (
-                                d.text().map(str::trim).join(""),
-                                t.text().map(str::trim).join(""),
-                                s.text().map(str::trim).join(""),
-                            )
-                        })
-                    })
-                });
-
-                if let Some((d, t, s)) = columns {
-                    let transformed: String = text_mapper.transoform(&s);
-                    let x = Addresses::parse(transformed.as_str()).map_err(|e| anyhow!("{e}"))?;
-                    println!("{}\t{t}\t{x:?}", text_mapper.transoform(&d));
-                    println!("\n\n-----------\n");
-                } else {
-                    tracing::warn!("malformed row #{i}: {row:?}");
+    fn print_weekly(&mut self) -> AnyhowResult<()> {
+        let weekly = self.pagination.page(aggregate(
+            self.by_day.iter().map(|(day, outage)| (*day, outage)),
+        ));
+        match self.output {
+            OutputFormat::Text => print!("{}", output::render_weekly_text(&weekly)),
+            OutputFormat::Table => println!("{}", output::render_weekly_table(&weekly)),
+            OutputFormat::Markdown => print!("{}", output::render_weekly_markdown(&weekly)),
+            OutputFormat::Ics => {}
+            #[cfg(feature = "serde")]
+            OutputFormat::Jsonl => {
+                for entry in weekly.iter() {
+                    output::write_jsonl(&mut std::io::stdout().lock(), entry)?;
                 }
             }
-        } else {
-            bail!("the page does not contain the data table");
+            #[cfg(feature = "serde")]
+            OutputFormat::Json => {
+                output::write_json(&mut std::io::stdout().lock(), &weekly, &self.warnings)?;
+            }
+            // the streets are merged over the days anyway
+            #[cfg(feature = "serde")]
+            OutputFormat::JsonByStreet => {
+                let outages = self.by_day.iter().map(|(_, outage)| outage);
+                output::write_json_by_street(&mut std::io::stdout().lock(), outages)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl PageSink for Printer {
+    fn page(&mut self, day: usize, page: &ParsedPage) -> AnyhowResult<()> {
+        #[cfg(feature = "serde")]
+        self.warnings.extend(
+            page.malformed_rows
+                .iter()
+                .map(|row| RowWarning::new(day, row)),
+        );
+        if let Some(metrics) = self.day_metrics.iter_mut().find(|m| m.day == day) {
+            metrics.fetched = true;
+            metrics.stats = page.stats;
+            metrics.outages = page.outages.len();
+        }
+
+        if self.weekly {
+            self.by_day
+                .extend(page.outages.iter().map(|outage| (day, outage.clone())));
+            return Ok(());
         }
+        self.print_page(day, page)
     }
 
-    Ok(())
+    fn finish(&mut self) -> AnyhowResult<()> {
+        if self.weekly {
+            self.print_weekly()?;
+        }
+
+        #[cfg(feature = "serde")]
+        if self.output == OutputFormat::Json && !self.weekly {
+            let outages = self
+                .collected
+                .iter()
+                .map(|(_, outage)| outage)
+                .collect::<Vec<_>>();
+            output::write_json(&mut std::io::stdout().lock(), &outages, &self.warnings)?;
+        }
+
+        #[cfg(feature = "serde")]
+        if self.output == OutputFormat::JsonByStreet && !self.weekly {
+            let outages = self.collected.iter().map(|(_, outage)| outage);
+            output::write_json_by_street(&mut std::io::stdout().lock(), outages)?;
+        }
+
+        if self.output == OutputFormat::Ics {
+            let events = self
+                .collected
+                .iter()
+                .map(|(date, outage)| (*date, outage))
+                .collect::<Vec<_>>();
+            print!(
+                "{}",
+                output::render_ics(&events, Local::now().naive_local())
+            );
+        }
+
+        if let Some(path) = &self.metrics_file {
+            let contents = metrics::render_metrics(&self.city, &self.day_metrics);
+            metrics::write_atomically(path, &contents)?;
+        }
+        Ok(())
+    }
 }
@@ -0,0 +1,89 @@
+//! The metrics of a run in the Prometheus text format, to be picked up by
+//! the textfile collector of the node exporter.
+use std::fmt::Write as _;
+use std::io::Write as _;
+use std::path::Path;
+
+use crate::scrape::PageStats;
+
+/// What happened to a day page during a run. `outages` counts the outages
+/// which passed the filter.
+#[derive(Eq, PartialEq, Clone, Copy, Default, Debug)]
+pub struct DayMetrics {
+    pub day: usize,
+    pub fetched: bool,
+    pub stats: PageStats,
+    pub outages: usize,
+}
+
+impl DayMetrics {
+    /// The metrics of a page which has not been fetched yet.
+    pub fn new(day: usize) -> Self {
+        Self {
+            day,
+            ..Default::default()
+        }
+    }
+}
+
+type Metric = (&'static str, &'static str, fn(&DayMetrics) -> usize);
+
+static METRICS: &[Metric] = &[
+    (
+        "beobot_pages_fetched",
+        "Whether the day page was fetched.",
+        |m| usize::from(m.fetched),
+    ),
+    (
+        "beobot_rows_parsed",
+        "The rows of the day page parsed into outages.",
+        |m| m.stats.parsed,
+    ),
+    (
+        "beobot_rows_malformed",
+        "The malformed rows of the day page.",
+        |m| m.stats.malformed,
+    ),
+    (
+        "beobot_outages_total",
+        "The outages of the day page passing the filter.",
+        |m| m.outages,
+    ),
+];
+
+/// Renders the metrics of the day pages of the city, every metric is
+/// labeled with the city and the day.
+pub fn render_metrics(city: &str, days: &[DayMetrics]) -> String {
+    let mut output = String::new();
+    for (name, help, value) in METRICS {
+        // writing into a string does not fail
+        let _ = writeln!(output, "# HELP {name} {help}");
+        let _ = writeln!(output, "# TYPE {name} gauge");
+        for metrics in days {
+            let _ = writeln!(
+                output,
+                "{name}{{city=\"{city}\",day=\"{}\"}} {}",
+                metrics.day,
+                value(metrics)
+            );
+        }
+    }
+    output
+}
+
+/// Writes the file through a temporary file next to it, which is renamed
+/// over it, so a reader never sees a partially written file.
+pub fn write_atomically(path: &Path, contents: &str) -> std::io::Result<()> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| std::io::Error::other(format!("{} is not a file", path.display())))?;
+    let mut temp_name = std::ffi::OsString::from(".");
+    temp_name.push(file_name);
+    temp_name.push(".tmp");
+    let temp_path = path.with_file_name(temp_name);
+
+    let mut file = std::fs::File::create(&temp_path)?;
+    file.write_all(contents.as_bytes())?;
+    file.sync_all()?;
+    std::fs::rename(&temp_path, path)
+}
@@ -0,0 +1,543 @@
+//! Rendering of the scraped outages.
+use chrono::{NaiveDate, NaiveDateTime, TimeDelta};
+use clap::ValueEnum;
+use comfy_table::{ContentArrangement, Table};
+use itertools::Itertools;
+
+use crate::addresses::Addresses;
+use crate::pages::DayPage;
+use crate::scrape::{MalformedRow, Outage};
+use crate::weekly::WeeklyOutage;
+
+/// The table width used when the output is not a terminal.
+const DEFAULT_TABLE_WIDTH: u16 = 120;
+
+#[derive(ValueEnum, Eq, PartialEq, Clone, Copy, Debug)]
+pub enum OutputFormat {
+    /// The municipality and the time of each outage followed by its
+    /// streets, a line per street.
+    Text,
+    /// An aligned table with a row per street.
+    Table,
+    /// An iCalendar file with an event per outage.
+    Ics,
+    /// A GitHub flavored Markdown table with a row per street.
+    Markdown,
+    /// A JSON object per outage and line, printed as soon as it is parsed.
+    #[cfg(feature = "serde")]
+    Jsonl,
+    /// A single JSON document with the outages and the warnings about the
+    /// skipped rows, printed once all the pages are processed.
+    #[cfg(feature = "serde")]
+    Json,
+    /// A single JSON object mapping the street names, sorted, to the
+    /// outage times and the house numbers of the street.
+    #[cfg(feature = "serde")]
+    JsonByStreet,
+}
+
+/// The longest content line allowed by RFC 5545, in octets.
+const ICS_LINE_LIMIT: usize = 75;
+
+const ICS_DATE_TIME: &str = "%Y%m%dT%H%M%S";
+
+/// Renders the outages as a table with a row per street.
+/// Rows whose addresses cannot be parsed are left out.
+pub fn render_table(outages: &[Outage]) -> String {
+    let mut table = Table::new();
+    table
+        .set_content_arrangement(ContentArrangement::Dynamic)
+        .set_header(vec!["Street", "Time", "Numbers"]);
+    if table.width().is_none() {
+        table.set_width(DEFAULT_TABLE_WIDTH);
+    }
+
+    for outage in outages {
+        if let Ok(addresses) = outage.addresses() {
+            for record in &addresses {
+                table.add_row(vec![
+                    record.street().to_owned(),
+                    outage.windows(),
+                    record.numbers().iter().join(", "),
+                ]);
+            }
+        }
+    }
+
+    table.to_string()
+}
+
+/// Renders the consolidated outages as a table with a row per street and
+/// set of numbers.
+pub fn render_weekly_table(weekly: &[WeeklyOutage]) -> String {
+    let mut table = Table::new();
+    table
+        .set_content_arrangement(ContentArrangement::Dynamic)
+        .set_header(vec!["Street", "Numbers", "Days"]);
+    if table.width().is_none() {
+        table.set_width(DEFAULT_TABLE_WIDTH);
+    }
+
+    for entry in weekly {
+        table.add_row(vec![
+            entry.street.clone(),
+            entry.numbers.join(", "),
+            entry.occurrences.iter().join("\n"),
+        ]);
+    }
+
+    table.to_string()
+}
+
+/// Renders the outages as a Markdown table with a row per street.
+/// Rows whose addresses cannot be parsed are left out.
+pub fn render_markdown(outages: &[Outage]) -> String {
+    let rows = outages.iter().flat_map(|outage| {
+        outage
+            .addresses()
+            .into_iter()
+            .flatten()
+            .map(|record| {
+                [
+                    record.street().to_owned(),
+                    outage.windows(),
+                    record.numbers().iter().join(", "),
+                ]
+            })
+            .collect::<Vec<_>>()
+    });
+    markdown_table(["Street", "Time", "Numbers"], rows)
+}
+
+/// Renders the consolidated outages as a Markdown table with a row per
+/// street and set of numbers.
+pub fn render_weekly_markdown(weekly: &[WeeklyOutage]) -> String {
+    let rows = weekly.iter().map(|entry| {
+        [
+            entry.street.clone(),
+            entry.numbers.join(", "),
+            entry.occurrences.iter().join("; "),
+        ]
+    });
+    markdown_table(["Street", "Numbers", "Days"], rows)
+}
+
+fn markdown_table<const N: usize>(
+    header: [&str; N],
+    rows: impl IntoIterator<Item = [String; N]>,
+) -> String {
+    let mut output = format!("| {} |\n", header.join(" | "));
+    output.push_str(&format!("|{}\n", "---|".repeat(N)));
+    for row in rows {
+        output.push_str(&format!(
+            "| {} |\n",
+            row.iter().map(|cell| markdown_escape(cell)).join(" | ")
+        ));
+    }
+    output
+}
+
+/// Escapes the pipes, which would end a table cell, and keeps a cell on
+/// a single line.
+fn markdown_escape(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+/// Renders the streets of an outage as an indented list with a line per
+/// street, the streets listed without numbers are marked as whole.
+pub fn render_addresses_text(addresses: &Addresses) -> String {
+    addresses
+        .into_iter()
+        .map(|record| {
+            let street = match record.note() {
+                Some(note) => format!("{} ({note})", record.street()),
+                None => record.street().to_owned(),
+            };
+            if record.is_whole_street() {
+                format!("    {street}: whole street\n")
+            } else {
+                format!("    {street}: {}\n", record.numbers().iter().join(", "))
+            }
+        })
+        .collect()
+}
+
+/// Renders the consolidated outages as tab separated lines.
+pub fn render_weekly_text(weekly: &[WeeklyOutage]) -> String {
+    weekly
+        .iter()
+        .map(|entry| {
+            format!(
+                "{}\t{}\t{}\n",
+                entry.street,
+                entry.numbers.join(","),
+                entry.occurrences.iter().join("; ")
+            )
+        })
+        .collect()
+}
+
+/// The version of the shape of the JSON and JSON lines output, written as
+/// the `schema_version` field. It is bumped on every change of the output
+/// which breaks its readers.
+pub const OUTPUT_SCHEMA_VERSION: u32 = 1;
+
+/// Writes the value, e.g. an outage, as a single line of JSON and flushes
+/// the writer, so the line reaches the reader right away. The value has to
+/// be an object, the `schema_version` field is added to it.
+#[cfg(feature = "serde")]
+pub fn write_jsonl<W, T>(writer: &mut W, value: &T) -> anyhow::Result<()>
+where
+    W: std::io::Write,
+    T: serde::Serialize,
+{
+    #[derive(serde::Serialize)]
+    struct Line<'a, T> {
+        schema_version: u32,
+        #[serde(flatten)]
+        value: &'a T,
+    }
+
+    let line = Line {
+        schema_version: OUTPUT_SCHEMA_VERSION,
+        value,
+    };
+    serde_json::to_writer(&mut *writer, &line)?;
+    writer.write_all(b"\n")?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// A skipped row of a day page, reported in the JSON document.
+#[derive(Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct RowWarning {
+    pub day: usize,
+    pub row: usize,
+    pub text: String,
+}
+
+impl RowWarning {
+    pub fn new(day: usize, malformed: &MalformedRow) -> Self {
+        Self {
+            day,
+            row: malformed.row,
+            text: malformed.text.clone(),
+        }
+    }
+}
+
+/// Writes a JSON document with the `schema_version`, the `outages`, or the
+/// weekly entries, and the `warnings` about the skipped rows.
+#[cfg(feature = "serde")]
+pub fn write_json<W, T>(
+    writer: &mut W,
+    outages: &[T],
+    warnings: &[RowWarning],
+) -> anyhow::Result<()>
+where
+    W: std::io::Write,
+    T: serde::Serialize,
+{
+    #[derive(serde::Serialize)]
+    struct Document<'a, T> {
+        schema_version: u32,
+        outages: &'a [T],
+        warnings: &'a [RowWarning],
+    }
+
+    let document = Document {
+        schema_version: OUTPUT_SCHEMA_VERSION,
+        outages,
+        warnings,
+    };
+    serde_json::to_writer(&mut *writer, &document)?;
+    writer.write_all(b"\n")?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// The outages of a street in the JSON document keyed by street.
+#[cfg(feature = "serde")]
+#[derive(Eq, PartialEq, Clone, Default, Debug, serde::Serialize)]
+pub struct StreetOutages {
+    /// The distinct windows of the outages, comma separated.
+    pub time: String,
+    /// The house numbers of all the outages of the street.
+    pub numbers: Vec<String>,
+}
+
+/// Groups the streets of the outages by name. The numbers of a street
+/// listed more than once are concatenated in order.
+/// Rows whose addresses cannot be parsed are left out.
+#[cfg(feature = "serde")]
+pub fn group_by_street<'a, I>(outages: I) -> std::collections::BTreeMap<String, StreetOutages>
+where
+    I: IntoIterator<Item = &'a Outage>,
+{
+    let mut streets = std::collections::BTreeMap::<String, (Vec<String>, Vec<String>)>::new();
+    for outage in outages {
+        let Ok(addresses) = outage.addresses() else {
+            continue;
+        };
+        let windows = outage.windows();
+        for record in &addresses {
+            let (times, numbers) = streets.entry(record.street().to_owned()).or_default();
+            if !times.contains(&windows) {
+                times.push(windows.clone());
+            }
+            numbers.extend(record.numbers().iter().map(ToString::to_string));
+        }
+    }
+    streets
+        .into_iter()
+        .map(|(street, (times, numbers))| {
+            let time = times.join(", ");
+            (street, StreetOutages { time, numbers })
+        })
+        .collect()
+}
+
+/// Writes a JSON object mapping the street names of the outages, sorted,
+/// to their times and numbers.
+#[cfg(feature = "serde")]
+pub fn write_json_by_street<'a, W, I>(writer: &mut W, outages: I) -> anyhow::Result<()>
+where
+    W: std::io::Write,
+    I: IntoIterator<Item = &'a Outage>,
+{
+    serde_json::to_writer(&mut *writer, &group_by_street(outages))?;
+    writer.write_all(b"\n")?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Writes the urls of the day pages, one per line.
+pub fn write_urls<W: std::io::Write>(writer: &mut W, pages: &[DayPage]) -> std::io::Result<()> {
+    for page in pages {
+        writeln!(writer, "{}", page.url)?;
+    }
+    Ok(())
+}
+
+/// Renders the outages as an iCalendar file, an outage on its date is
+/// an event. The times are floating local times as published.
+/// An outage with several time windows becomes an event per window.
+/// Outages without a parsed time range are left out, and an outage whose
+/// range ends before it starts is taken to end on the next day.
+pub fn render_ics(events: &[(NaiveDate, &Outage)], stamp: NaiveDateTime) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_owned(),
+        "VERSION:2.0".to_owned(),
+        "PRODID:-//beobot//power outages//EN".to_owned(),
+    ];
+
+    let windows = events.iter().flat_map(|(date, outage)| {
+        outage
+            .time_ranges
+            .iter()
+            .map(move |range| (date, range, outage))
+    });
+    for (index, (date, range, outage)) in windows.enumerate() {
+        let start = date.and_time(range.start());
+        let mut end = date.and_time(range.end());
+        if range.is_overnight() {
+            end += TimeDelta::days(1);
+        }
+
+        let mut description = outage.streets.clone();
+        if let Some(station) = &outage.station {
+            description = format!("{station}\n{description}");
+        }
+
+        lines.push("BEGIN:VEVENT".to_owned());
+        lines.push(format!("UID:{}-{index}@beobot", date.format("%Y%m%d")));
+        lines.push(format!("DTSTAMP:{}", stamp.format(ICS_DATE_TIME)));
+        lines.push(format!("DTSTART:{}", start.format(ICS_DATE_TIME)));
+        lines.push(format!("DTEND:{}", end.format(ICS_DATE_TIME)));
+        lines.push(format!(
+            "SUMMARY:{}",
+            ics_escape(&format!("Power outage: {}", outage.municipality))
+        ));
+        lines.push(format!("DESCRIPTION:{}", ics_escape(&description)));
+        lines.push("END:VEVENT".to_owned());
+    }
+
+    lines.push("END:VCALENDAR".to_owned());
+    lines.iter().map(|line| ics_fold(line)).join("")
+}
+
+/// Escapes the characters which are special in iCalendar text values.
+fn ics_escape(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => output.push_str("\\\\"),
+            ';' => output.push_str("\\;"),
+            ',' => output.push_str("\\,"),
+            '\n' => output.push_str("\\n"),
+            '\r' => {}
+            c => output.push(c),
+        }
+    }
+    output
+}
+
+/// Folds a content line into lines of at most [`ICS_LINE_LIMIT`] octets,
+/// never splitting a character, and terminates it with CRLF.
+fn ics_fold(line: &str) -> String {
+    let mut output = String::with_capacity(line.len() + 2);
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > ICS_LINE_LIMIT {
+            output.push_str("\r\n ");
+            length = 1;
+        }
+        output.push(c);
+        length += c.len_utf8();
+    }
+    output.push_str("\r\n");
+    output
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::timeint::TimeRange;
+
+    #[test]
+    fn test_render_table() {
+        let outage = Outage {
+            municipality: "ZEMUN".to_owned(),
+            time: "08:30-14:00".to_owned(),
+            streets: "MALA: 2-10,1,  BATAJNIČKI DRUM: BB,261-265,269,".to_owned(),
+            ..Default::default()
+        };
+
+        let rendered = render_table(&[outage]);
+        let lines = rendered.lines().collect::<Vec<_>>();
+        assert!(lines[1].contains("Street"));
+        assert!(lines[1].contains("Time"));
+        assert!(lines[1].contains("Numbers"));
+        assert!(rendered.lines().any(|line| line.contains("MALA")
+            && line.contains("08:30-14:00")
+            && line.contains("2-10, 1")));
+        assert!(rendered.contains("BB, 261-265, 269"));
+    }
+
+    #[test]
+    fn test_render_addresses_text() {
+        let addresses =
+            Addresses::parse("MALA (DEO): 2-10,1,  BRODSKA,  PILOTSKA: BB,36A,").unwrap();
+        assert_eq!(
+            render_addresses_text(&addresses),
+            "    MALA (DEO): 2-10, 1\n    BRODSKA: whole street\n    PILOTSKA: BB, 36A\n"
+        );
+    }
+
+    #[test]
+    fn test_render_weekly() {
+        let outage = ics_outage("08:30-14:00");
+        let weekly = crate::weekly::aggregate([(0, &outage), (2, &outage)]);
+
+        let rendered = render_weekly_text(&weekly);
+        assert!(rendered.contains("MALA\t2-10,1\tday 0 08:30-14:00; day 2 08:30-14:00\n"));
+
+        let rendered = render_weekly_table(&weekly);
+        assert!(rendered.lines().any(|line| line.contains("Days")));
+        assert!(rendered.contains("day 2 08:30-14:00"));
+    }
+
+    #[test]
+    fn test_render_table_several_windows() {
+        let outage = ics_outage("08:00-10:00,13:00-15:00");
+        assert_eq!(outage.time_ranges.len(), 2);
+
+        let outages = [outage];
+        let rendered = render_table(&outages);
+        assert!(rendered.contains("08:00-10:00, 13:00-15:00"));
+        let rendered = render_markdown(&outages);
+        assert!(rendered.contains("| MALA | 08:00-10:00, 13:00-15:00 | 2-10, 1 |"));
+    }
+
+    #[test]
+    fn test_render_markdown() {
+        let outage = Outage {
+            municipality: "ZEMUN".to_owned(),
+            time: "08:30-14:00".to_owned(),
+            streets: "MALA|VELIKA: 2-10,1,  BATAJNIČKI DRUM: BB,261-265,269,".to_owned(),
+            ..Default::default()
+        };
+
+        let rendered = render_markdown(&[outage]);
+        assert!(rendered.starts_with("| Street | Time | Numbers |\n|---|---|---|\n"));
+        assert!(rendered.contains("| MALA\\|VELIKA | 08:30-14:00 | 2-10, 1 |\n"));
+        assert!(rendered.contains("| BATAJNIČKI DRUM | 08:30-14:00 | BB, 261-265, 269 |\n"));
+    }
+
+    fn ics_outage(time: &str) -> Outage {
+        Outage {
+            municipality: "ZEMUN".to_owned(),
+            time: time.to_owned(),
+            time_ranges: TimeRange::parse_list(time).unwrap_or_default(),
+            streets: "MALA: 2-10,1,  BATAJNIČKI DRUM: BB,261-265,269,".to_owned(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_render_ics() {
+        let date = NaiveDate::from_ymd_opt(2022, 6, 20).unwrap();
+        let stamp = date.and_hms_opt(6, 0, 0).unwrap();
+        let outage = ics_outage("08:30-14:00");
+        let unparsed = ics_outage("ceo dan");
+
+        let rendered = render_ics(&[(date, &outage), (date, &unparsed)], stamp);
+        assert!(rendered.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(rendered.ends_with("END:VCALENDAR\r\n"));
+        assert_eq!(rendered.matches("BEGIN:VEVENT").count(), 1);
+        assert!(rendered.contains("\r\nDTSTART:20220620T083000\r\n"));
+        assert!(rendered.contains("\r\nDTEND:20220620T140000\r\n"));
+        assert!(rendered.contains("\r\nDTSTAMP:20220620T060000\r\n"));
+        assert!(rendered.contains("SUMMARY:Power outage: ZEMUN\r\n"));
+        assert!(rendered.contains("DESCRIPTION:MALA: 2-10\\,1\\,"));
+        assert!(rendered
+            .split("\r\n")
+            .all(|line| line.len() <= ICS_LINE_LIMIT));
+    }
+
+    #[test]
+    fn test_render_ics_several_windows() {
+        let date = NaiveDate::from_ymd_opt(2022, 6, 20).unwrap();
+        let stamp = date.and_hms_opt(6, 0, 0).unwrap();
+        let outage = ics_outage("08:00-10:00,13:00-15:00");
+
+        let rendered = render_ics(&[(date, &outage)], stamp);
+        assert_eq!(rendered.matches("BEGIN:VEVENT").count(), 2);
+        assert!(rendered.contains("\r\nDTSTART:20220620T080000\r\nDTEND:20220620T100000\r\n"));
+        assert!(rendered.contains("\r\nDTSTART:20220620T130000\r\nDTEND:20220620T150000\r\n"));
+        assert!(rendered.contains("UID:20220620-1@beobot"));
+    }
+
+    #[test]
+    fn test_render_ics_overnight() {
+        let date = NaiveDate::from_ymd_opt(2022, 6, 30).unwrap();
+        let stamp = date.and_hms_opt(6, 0, 0).unwrap();
+        let outage = ics_outage("22:00-06:00");
+
+        let rendered = render_ics(&[(date, &outage)], stamp);
+        assert!(rendered.contains("\r\nDTSTART:20220630T220000\r\n"));
+        assert!(rendered.contains("\r\nDTEND:20220701T060000\r\n"));
+    }
+
+    #[test]
+    fn test_ics_fold() {
+        let line = "Ž".repeat(50);
+        let folded = ics_fold(&line);
+        let lines = folded.trim_end().split("\r\n").collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+        assert!(lines.iter().all(|line| line.len() <= ICS_LINE_LIMIT));
+        assert_eq!(lines.concat().replace(' ', ""), line);
+    }
+}
@@ -0,0 +1,43 @@
+//! The day pages the outages are published on. Every city has a page per
+//! day, starting with today.
+
+/// The site the day pages are published on.
+pub const DEFAULT_BASE_URL: &str = "https://elektrodistribucija.rs";
+
+/// How many day pages a city has.
+pub const DAYS: usize = 4;
+
+/// The most day pages which can be asked for with `--days-ahead`.
+pub const MAX_DAYS: usize = 14;
+
+/// The cities built into the configuration.
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub enum City {
+    Beograd,
+    NoviSad,
+}
+
+impl City {
+    /// The name of the city in the configuration, as given to `--city`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            City::Beograd => "beograd",
+            City::NoviSad => "novi-sad",
+        }
+    }
+
+    /// The prefix of the day page names of the city.
+    pub(crate) fn page_prefix(&self) -> &'static str {
+        match self {
+            City::Beograd => "",
+            City::NoviSad => "NoviSad_",
+        }
+    }
+}
+
+/// A day page to fetch, `day` counts the days from today.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct DayPage {
+    pub day: usize,
+    pub url: String,
+}
@@ -0,0 +1,847 @@
+//! The module extracts the outages table out of a day page and turns its
+//! rows into [`Outage`] values.
+use anyhow::{anyhow, bail, Context, Result as AnyhowResult};
+use chrono::NaiveDate;
+use futures::stream::{self, Stream, StreamExt};
+use itertools::Itertools;
+use nom::character::complete::{digit1, one_of};
+use nom::combinator::{all_consuming, map_res, recognize};
+use nom::error::Error;
+use nom::multi::separated_list1;
+use scraper::{ElementRef, Html, Node, Selector};
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::time::Duration;
+use tracing::Instrument;
+
+use crate::addresses::Addresses;
+use crate::config::Selectors;
+use crate::pages::DayPage;
+use crate::script_mapper::Mapper;
+use crate::source::PageSource;
+use crate::timeint::{TimeParseError, TimeRange};
+
+/// A single row of the outages table.
+///
+/// All the text is transliterated into upper case Latin script except
+/// for the time column which is kept as is. `time_ranges` holds the parsed
+/// time column, a cell may list several windows, e.g.
+/// `08:00-10:00,13:00-15:00`. It is empty when the column is not parsed.
+/// `station` names the affected substation and `consumers` is the number
+/// of affected consumers, on pages which list them.
+#[derive(Eq, PartialEq, Clone, Default, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Outage {
+    pub municipality: String,
+    pub time: String,
+    pub time_ranges: Vec<TimeRange>,
+    pub streets: String,
+    pub station: Option<String>,
+    pub consumers: Option<u32>,
+}
+
+impl Outage {
+    /// Parses the affected addresses out of the streets column.
+    pub fn addresses(&self) -> AnyhowResult<Addresses<'_>> {
+        Addresses::parse(self.streets.as_str()).map_err(|e| anyhow!("{e}"))
+    }
+
+    /// The time windows for display, as `08:00-10:00, 13:00-15:00`. The
+    /// time column is shown as is when it is not parsed.
+    pub fn windows(&self) -> String {
+        if self.time_ranges.is_empty() {
+            self.time.clone()
+        } else {
+            self.time_ranges.iter().join(", ")
+        }
+    }
+}
+
+/// The settings of the HTTP client used to download the day pages.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct ClientConfig {
+    /// The limit for a whole request, from connecting to reading the body.
+    pub timeout: Duration,
+    pub user_agent: String,
+    /// How many times a request which failed to complete is repeated.
+    /// A response with an error status is not retried.
+    pub retries: u32,
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(30),
+            user_agent: concat!("beobot/", env!("CARGO_PKG_VERSION")).to_owned(),
+            retries: 2,
+        }
+    }
+}
+
+/// Builds the HTTP client to download the day pages with.
+pub fn build_client(cfg: &ClientConfig) -> reqwest::Result<reqwest::Client> {
+    reqwest::Client::builder()
+        .timeout(cfg.timeout)
+        .user_agent(cfg.user_agent.as_str())
+        .build()
+}
+
+/// The pause before the first retry, it grows with every next attempt.
+const RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// Downloads a day page. A response with a non-successful status is
+/// logged and `None` is returned, so the page is skipped.
+pub async fn fetch(client: &reqwest::Client, url: &str) -> AnyhowResult<Option<String>> {
+    fetch_with_retries(client, url, 0).await
+}
+
+/// Same as [`fetch`] but a request which failed to complete, e.g. timed out,
+/// is repeated up to `retries` times.
+pub async fn fetch_with_retries(
+    client: &reqwest::Client,
+    url: &str,
+    retries: u32,
+) -> AnyhowResult<Option<String>> {
+    let mut attempt = 0;
+    loop {
+        match try_fetch(client, url).await {
+            Err(e) if attempt < retries => {
+                attempt += 1;
+                tracing::warn!("cannot fetch {url}, retrying ({attempt}/{retries}): {e}");
+                tokio::time::sleep(RETRY_DELAY * attempt).await;
+            }
+            result => return Ok(result?),
+        }
+    }
+}
+
+async fn try_fetch(client: &reqwest::Client, url: &str) -> reqwest::Result<Option<String>> {
+    let response = client.get(url).send().await?;
+    let status = response.status();
+    if !status.is_success() {
+        tracing::error!("cannot fetch {url}: {status}");
+        return Ok(None);
+    }
+    Ok(Some(response.text().await?))
+}
+
+/// A downloaded page along with the response metadata.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct Page {
+    pub status: u16,
+    /// The url the page was served from, after following the redirects.
+    pub final_url: String,
+    /// The body decoded with the charset of the response, UTF-8 if it
+    /// declares none.
+    pub body: String,
+}
+
+/// Downloads a page whatever its status is, for the callers which want to
+/// inspect the response. Only a request which fails to complete is an
+/// error.
+pub async fn fetch_page(client: &reqwest::Client, url: &str) -> AnyhowResult<Page> {
+    let response = client.get(url).send().await?;
+    let status = response.status().as_u16();
+    let final_url = response.url().to_string();
+    let body = response.text().await?;
+    Ok(Page {
+        status,
+        final_url,
+        body,
+    })
+}
+
+/// The span covering the fetching and parsing of a day page, so the row
+/// warnings carry the page they come from.
+pub fn page_span(url: &str, day: usize) -> tracing::Span {
+    tracing::info_span!("page", url = %url, day)
+}
+
+/// The context of an error processing a day page, so a failure in the
+/// strict mode tells which page the broken row is on.
+pub(crate) fn page_context(day: usize, url: &str) -> String {
+    format!("day {day} ({url})")
+}
+
+/// The byte order mark some editors put at the start of a UTF-8 file.
+const BOM: char = '\u{feff}';
+
+/// Reads a day page saved to a file, e.g. for `--file`. The text is
+/// cleaned up with [`normalize_page_text`].
+pub fn read_page_file(path: &std::path::Path) -> AnyhowResult<String> {
+    let text =
+        std::fs::read_to_string(path).with_context(|| format!("cannot read {}", path.display()))?;
+    Ok(normalize_page_text(&text))
+}
+
+/// Lists the saved day pages to parse: the files as they are given and
+/// the `.html` and `.htm` files of the directories, sorted by name.
+pub fn page_files(paths: &[PathBuf]) -> AnyhowResult<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for path in paths {
+        if !path.is_dir() {
+            files.push(path.clone());
+            continue;
+        }
+        let entries =
+            std::fs::read_dir(path).with_context(|| format!("cannot list {}", path.display()))?;
+        let mut pages = Vec::new();
+        for entry in entries {
+            let entry = entry.with_context(|| format!("cannot list {}", path.display()))?;
+            let page = entry.path();
+            let is_page = page
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| {
+                    ext.eq_ignore_ascii_case("html") || ext.eq_ignore_ascii_case("htm")
+                });
+            if is_page && page.is_file() {
+                pages.push(page);
+            }
+        }
+        pages.sort();
+        files.extend(pages);
+    }
+    Ok(files)
+}
+
+/// Reads the saved day pages in order and hands the text of every page
+/// over to `on_page` within the page span, the pages numbered as days from
+/// 0. A file which cannot be read or whose page cannot be processed is
+/// logged and skipped, the rest of the batch goes on. Returns how many
+/// files failed.
+pub fn scrape_files_with<F>(files: &[PathBuf], mut on_page: F) -> usize
+where
+    F: FnMut(usize, &str) -> AnyhowResult<PageStats>,
+{
+    let mut failed = 0;
+    for (day, path) in files.iter().enumerate() {
+        let span = page_span(&path.display().to_string(), day);
+        let result = read_page_file(path).and_then(|body| {
+            span.in_scope(|| on_page(day, &body))
+                .with_context(|| page_context(day, &path.display().to_string()))
+        });
+        match result {
+            Ok(stats) => tracing::info!(parent: &span, "{}: {stats}", path.display()),
+            Err(e) => {
+                tracing::error!(parent: &span, "skipping {}: {e:#}", path.display());
+                failed += 1;
+            }
+        }
+    }
+    failed
+}
+
+/// Drops the leading byte order mark and turns the Windows line endings
+/// into plain ones, as a page saved on Windows has them.
+pub fn normalize_page_text(text: &str) -> String {
+    text.strip_prefix(BOM).unwrap_or(text).replace("\r\n", "\n")
+}
+
+/// How many pages [`stream_outages`] fetches at once.
+const STREAM_CONCURRENCY: usize = 4;
+
+/// Fetches and parses the day pages concurrently and yields the outages
+/// of every page as soon as the page is parsed, so the pages may come out
+/// of order. A page which cannot be fetched or parsed yields an error, the
+/// other pages are not affected.
+pub fn stream_outages<'a, S: PageSource>(
+    source: &'a S,
+    page_parser: &'a PageParser,
+    day_pages: &'a [DayPage],
+) -> impl Stream<Item = AnyhowResult<Outage>> + 'a {
+    stream::iter(day_pages)
+        .map(move |DayPage { day, url }| {
+            let span = page_span(url, *day);
+            async move {
+                let outages = match source.fetch(url).instrument(span.clone()).await {
+                    Ok(Some(body)) => match span.in_scope(|| page_parser.parse_page(&body)) {
+                        Ok(page) => page.outages.into_iter().map(Ok).collect(),
+                        Err(e) => vec![Err(e.context(page_context(*day, url)))],
+                    },
+                    Ok(None) => Vec::new(),
+                    Err(e) => vec![Err(e)],
+                };
+                stream::iter(outages)
+            }
+        })
+        .buffer_unordered(STREAM_CONCURRENCY)
+        .flatten()
+}
+
+/// Counts of the rows seen while processing a page.
+#[derive(Eq, PartialEq, Clone, Copy, Default, Debug)]
+pub struct PageStats {
+    pub total: usize,
+    pub parsed: usize,
+    pub malformed: usize,
+}
+
+impl fmt::Display for PageStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}/{} rows parsed, {} malformed",
+            self.parsed, self.total, self.malformed
+        )
+    }
+}
+
+/// A row of the outages table which was skipped. `row` is the index of
+/// the row among the data rows and `text` the text of its cells.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct MalformedRow {
+    pub row: usize,
+    pub text: String,
+}
+
+/// The outages extracted from a page along with the page statistics, the
+/// skipped rows and the date the page announces the outages for, if it
+/// could be found.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct ParsedPage {
+    pub date: Option<NaiveDate>,
+    pub outages: Vec<Outage>,
+    pub stats: PageStats,
+    pub malformed_rows: Vec<MalformedRow>,
+}
+
+/// The selector tried after the configured table selector, for when the
+/// page gains wrapper elements. The table it matches has to have the
+/// expected header.
+const FALLBACK_TABLE_SELECTOR: &str = "table";
+
+/// The (transliterated) header columns of the outages table.
+static TABLE_HEADERS: &[&str] = &["OPŠTINA", "VREME", "ULICE"];
+
+struct TableSelector {
+    css: String,
+    selector: Selector,
+    check_header: bool,
+}
+
+/// Holds the selectors and the transliteration table needed to process
+/// a day page.
+pub struct PageParser {
+    mapper: Mapper,
+    table_selectors: Vec<TableSelector>,
+    header_selector: Selector,
+    tr_selector: Selector,
+    td_selector: Selector,
+    strict: bool,
+}
+
+impl PageParser {
+    pub fn new() -> AnyhowResult<Self> {
+        Self::with_selectors(&Selectors::default())
+    }
+
+    /// Same as [`PageParser::new`] but with the given selectors. The table
+    /// selector is tried first, then any table with the expected header.
+    pub fn with_selectors(selectors: &Selectors) -> AnyhowResult<Self> {
+        let table_selectors = [
+            (selectors.table.as_str(), false),
+            (FALLBACK_TABLE_SELECTOR, true),
+        ]
+        .into_iter()
+        .map(|(css, check_header)| {
+            Selector::parse(css)
+                .map(|selector| TableSelector {
+                    css: css.to_owned(),
+                    selector,
+                    check_header,
+                })
+                .map_err(|e| anyhow!("{e:?}"))
+        })
+        .collect::<AnyhowResult<Vec<_>>>()?;
+        let header_selector = Selector::parse("tr:first-child").map_err(|e| anyhow!("{e:?}"))?;
+        let tr_selector = Selector::parse(&selectors.row).map_err(|e| anyhow!("{e:?}"))?;
+        let td_selector = Selector::parse(&selectors.cell).map_err(|e| anyhow!("{e:?}"))?;
+
+        Ok(Self {
+            mapper: Mapper::new(),
+            table_selectors,
+            header_selector,
+            tr_selector,
+            td_selector,
+            strict: false,
+        })
+    }
+
+    /// In the strict mode the first malformed row fails the page instead
+    /// of being reported and skipped.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Finds the outages table trying the selectors one by one and taking
+    /// the first table which has data rows.
+    fn find_data_table<'a>(&self, document: &'a Html) -> Option<ElementRef<'a>> {
+        for table_selector in self.table_selectors.iter() {
+            let found = document.select(&table_selector.selector).find(|table| {
+                (!table_selector.check_header || self.has_expected_header(table))
+                    && table.select(&self.tr_selector).next().is_some()
+            });
+
+            if let Some(table) = found {
+                tracing::debug!("data table matched by `{}`", table_selector.css);
+                return Some(table);
+            }
+        }
+        None
+    }
+
+    /// Checks that the first row of the table names the expected columns.
+    fn has_expected_header(&self, table: &ElementRef<'_>) -> bool {
+        table
+            .select(&self.header_selector)
+            .next()
+            .map(|header| {
+                let cells = header
+                    .select(&self.td_selector)
+                    .map(|td| self.mapper.transoform(&cell_text(&td)))
+                    .collect::<Vec<_>>();
+                cells.len() >= TABLE_HEADERS.len()
+                    && TABLE_HEADERS
+                        .iter()
+                        .zip(cells.iter())
+                        .all(|(expected, cell)| cell.contains(expected))
+            })
+            .unwrap_or(false)
+    }
+
+    /// Tells whether the page has the outages table, the rows are not
+    /// looked at.
+    pub fn has_data_table(&self, body: &str) -> bool {
+        let document = Html::parse_document(body);
+        self.find_data_table(&document).is_some()
+    }
+
+    /// Extracts all the outages from the HTML document of a day page.
+    /// Malformed rows are reported, counted and skipped.
+    pub fn parse_page(&self, body: &str) -> AnyhowResult<ParsedPage> {
+        let document = Html::parse_document(body);
+
+        let mut outages = Vec::new();
+        let mut malformed_rows = Vec::new();
+        let stats = self.walk_rows(
+            &document,
+            |outage| {
+                outages.push(outage);
+                Ok(())
+            },
+            |malformed| malformed_rows.push(malformed),
+        )?;
+
+        let date = find_page_date(&document);
+
+        Ok(ParsedPage {
+            date,
+            outages,
+            stats,
+            malformed_rows,
+        })
+    }
+
+    /// Same as [`PageParser::parse_page`] but hands every outage over as
+    /// soon as its row is parsed instead of collecting them.
+    pub fn for_each_outage<F>(&self, body: &str, on_outage: F) -> AnyhowResult<PageStats>
+    where
+        F: FnMut(Outage) -> AnyhowResult<()>,
+    {
+        let document = Html::parse_document(body);
+        self.walk_rows(&document, on_outage, |_| {})
+    }
+
+    fn walk_rows<F, M>(
+        &self,
+        document: &Html,
+        mut on_outage: F,
+        mut on_malformed: M,
+    ) -> AnyhowResult<PageStats>
+    where
+        F: FnMut(Outage) -> AnyhowResult<()>,
+        M: FnMut(MalformedRow),
+    {
+        let data_table = match self.find_data_table(document) {
+            Some(data_table) => data_table,
+            None => bail!("the page does not contain the data table"),
+        };
+
+        let mut stats = PageStats::default();
+        for (i, row) in data_table.select(&self.tr_selector).enumerate() {
+            stats.total += 1;
+            match self.parse_row(i, row) {
+                Ok(outage) => {
+                    stats.parsed += 1;
+                    on_outage(outage)?;
+                }
+                Err(reason) => {
+                    let text = row
+                        .select(&self.td_selector)
+                        .map(|td| cell_text(&td))
+                        .join(" | ");
+                    if self.strict {
+                        bail!("{reason:#}, the row: `{text}`");
+                    }
+                    tracing::warn!("{reason:#}, the row: `{text}`");
+                    stats.malformed += 1;
+                    on_malformed(MalformedRow { row: i, text });
+                }
+            }
+        }
+
+        Ok(stats)
+    }
+
+    /// Turns a table row into an outage. Rows which do not have the expected
+    /// columns or whose addresses cannot be parsed are rejected with the
+    /// reason, which tells the row and the transliterated streets cell.
+    fn parse_row(&self, i: usize, row: ElementRef<'_>) -> AnyhowResult<Outage> {
+        let columns = match extract_columns_with(&row, &self.td_selector) {
+            Some(columns) => columns,
+            None => bail!("malformed row #{i}: the columns are missing"),
+        };
+
+        let time_ranges = match parse_time_column(&columns.time) {
+            Ok(time_ranges) => time_ranges,
+            Err(e) => {
+                tracing::warn!("cannot parse time in row #{i}: {e}");
+                Vec::new()
+            }
+        };
+
+        let outage = Outage {
+            municipality: self.mapper.transoform(&columns.municipality),
+            time: columns.time,
+            time_ranges,
+            streets: self.mapper.transoform(&columns.streets),
+            station: columns
+                .station
+                .map(|station| self.mapper.transoform(&station)),
+            consumers: columns.consumers,
+        };
+
+        let addresses = outage
+            .addresses()
+            .with_context(|| format!("row #{i}: cannot parse the streets `{}`", outage.streets))?;
+        for warning in addresses.warnings() {
+            tracing::warn!("row #{i}: {warning}");
+        }
+        for record in &addresses {
+            if let Err(e) = record.validate() {
+                tracing::warn!("suspicious address in row #{i}: {e}");
+            }
+        }
+
+        Ok(outage)
+    }
+}
+
+fn td_selector() -> &'static Selector {
+    static TD_SELECTOR: OnceLock<Selector> = OnceLock::new();
+    TD_SELECTOR.get_or_init(|| Selector::parse("td").expect("td is a valid selector"))
+}
+
+/// The text of a cell, the text pieces are trimmed and joined and a line
+/// break becomes a space.
+fn cell_text(cell: &ElementRef<'_>) -> String {
+    let mut text = String::new();
+    for node in cell.descendants() {
+        match node.value() {
+            Node::Text(piece) => text.push_str(piece.trim()),
+            Node::Element(element) if element.name() == "br" => text.push(' '),
+            _ => {}
+        }
+    }
+    clean_cell_text(&text)
+}
+
+/// The entities and line breaks which are escaped twice on some pages and
+/// so survive the HTML parser as text.
+const LEFTOVER_MARKUP: [(&str, &str); 8] = [
+    ("&nbsp;", " "),
+    ("<br>", " "),
+    ("<br/>", " "),
+    ("<br />", " "),
+    ("&quot;", "\""),
+    ("&lt;", "<"),
+    ("&gt;", ">"),
+    ("&amp;", "&"),
+];
+
+/// Decodes the leftover entities and line breaks and turns the other
+/// whitespace, e.g. non-breaking spaces and newlines, into plain spaces.
+/// A stray byte order mark is dropped.
+fn clean_cell_text(text: &str) -> String {
+    let mut text = text.to_owned();
+    for (markup, replacement) in LEFTOVER_MARKUP {
+        if text.contains(markup) {
+            text = text.replace(markup, replacement);
+        }
+    }
+    text.chars()
+        .filter(|c| *c != BOM)
+        .map(|c| if c.is_whitespace() { ' ' } else { c })
+        .collect::<String>()
+        .trim()
+        .to_owned()
+}
+
+/// The text of the cells of an outages table row.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct Columns {
+    pub municipality: String,
+    pub time: String,
+    pub streets: String,
+    /// The optional fourth column naming the substation.
+    pub station: Option<String>,
+    /// The optional trailing column counting the affected consumers.
+    pub consumers: Option<u32>,
+}
+
+/// Returns the text of the cells of a table row, or `None` if the row has
+/// fewer than three cells. A trailing cell beyond the third one holding
+/// a number is taken as the consumers count. The fourth cell is taken as
+/// the station if it is present, not empty and not the consumers count,
+/// any further cells are ignored.
+pub fn extract_columns(row: &ElementRef<'_>) -> Option<Columns> {
+    extract_columns_with(row, td_selector())
+}
+
+/// Same as [`extract_columns`] but the cells are the elements matched by
+/// the selector.
+fn extract_columns_with(row: &ElementRef<'_>, td_selector: &Selector) -> Option<Columns> {
+    let mut cells = row.select(td_selector);
+    let municipality = cell_text(&cells.next()?);
+    let time = cell_text(&cells.next()?);
+    let streets = cell_text(&cells.next()?);
+
+    let mut extra = cells.map(|cell| cell_text(&cell)).collect::<Vec<_>>();
+    let consumers = extra.last().and_then(|text| parse_consumers_column(text));
+    if consumers.is_some() {
+        extra.pop();
+    }
+    let station = extra
+        .into_iter()
+        .next()
+        .filter(|station| !station.is_empty());
+
+    Some(Columns {
+        municipality,
+        time,
+        streets,
+        station,
+        consumers,
+    })
+}
+
+/// The date formats the page may use to announce the outages date.
+static DATE_FORMATS: &[&str] = &["%Y-%m-%d", "%d.%m.%Y"];
+
+/// Finds the first date mentioned in the page text.
+fn find_page_date(document: &Html) -> Option<NaiveDate> {
+    document.root_element().text().find_map(|text| {
+        text.char_indices()
+            .filter(|(_, c)| c.is_ascii_digit())
+            .find_map(|(i, _)| {
+                DATE_FORMATS.iter().find_map(|format| {
+                    NaiveDate::parse_and_remainder(&text[i..], format)
+                        .ok()
+                        .map(|(date, _)| date)
+                })
+            })
+    })
+}
+
+/// Parses the time windows of a row. An empty cell or a lone dash means
+/// the outage window is not given, which is not an error.
+fn parse_time_column(input: &str) -> Result<Vec<TimeRange>, TimeParseError> {
+    match input.trim() {
+        "" | "-" => Ok(Vec::new()),
+        input => TimeRange::parse_list(input),
+    }
+}
+
+/// Parses the consumers count of a row. The thousands may be separated
+/// with spaces or dots, so `1.234` and `1 234` are both `1234`.
+fn parse_consumers_column(input: &str) -> Option<u32> {
+    let separator = one_of::<_, _, Error<&str>>(" .\u{00A0}");
+    let mut parser = all_consuming(map_res(
+        recognize(separated_list1(separator, digit1)),
+        |count: &str| {
+            count
+                .chars()
+                .filter(char::is_ascii_digit)
+                .collect::<String>()
+                .parse::<u32>()
+        },
+    ));
+    parser(input.trim()).ok().map(|(_, count)| count)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use chrono::NaiveTime;
+
+    fn hm(hh: u32, mm: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hh, mm, 0).expect("valid time")
+    }
+
+    fn first_row(html: &str) -> Option<Columns> {
+        let fragment = Html::parse_fragment(html);
+        let tr_selector = Selector::parse("tr").unwrap();
+        let row = fragment.select(&tr_selector).next().expect("the row");
+        extract_columns(&row)
+    }
+
+    #[test]
+    fn test_extract_columns() {
+        let columns = first_row(
+            "<table><tr><td> Земун </td><td>08:30-14:00</td><td><b>Мала:</b> 2-10,</td></tr></table>",
+        );
+        assert_eq!(
+            columns,
+            Some(Columns {
+                municipality: "Земун".to_owned(),
+                time: "08:30-14:00".to_owned(),
+                streets: "Мала:2-10,".to_owned(),
+                station: None,
+                consumers: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_extract_columns_cleans_markup() {
+        let columns = first_row(
+            "<table><tr><td>Земун&nbsp;</td><td>08:30-14:00</td>\
+             <td>Мала:&nbsp;2-10,<br>Бродска: 2-18,&amp;nbsp;<BR/>Пилотска: 1</td></tr></table>",
+        )
+        .expect("the columns");
+        assert_eq!(columns.municipality, "Земун");
+        assert_eq!(columns.streets, "Мала: 2-10, Бродска: 2-18,  Пилотска: 1");
+
+        let streets = Mapper::new().transoform(&columns.streets);
+        let addresses = Addresses::parse(&streets).expect("parse the streets");
+        assert_eq!(
+            addresses
+                .into_iter()
+                .map(|record| record.street().to_owned())
+                .collect::<Vec<_>>(),
+            vec!["MALA", "BRODSKA", "PILOTSKA"]
+        );
+    }
+
+    #[test]
+    fn test_normalize_page_text() {
+        assert_eq!(
+            normalize_page_text("\u{feff}<html>\r\n<body>\r\n"),
+            "<html>\n<body>\n"
+        );
+        assert_eq!(normalize_page_text("<html>\n"), "<html>\n");
+        assert_eq!(clean_cell_text("\u{feff}Земун\r\n"), "Земун");
+    }
+
+    #[test]
+    fn test_extract_columns_missing_cell() {
+        let columns = first_row("<table><tr><td>Земун</td><td>08:30-14:00</td></tr></table>");
+        assert_eq!(columns, None);
+    }
+
+    #[test]
+    fn test_extract_columns_extra_cells() {
+        let columns =
+            first_row("<table><tr><td>a</td><td>b</td><td>c</td><td>d</td><td>e</td></tr></table>");
+        assert_eq!(
+            columns,
+            Some(Columns {
+                municipality: "a".to_owned(),
+                time: "b".to_owned(),
+                streets: "c".to_owned(),
+                station: Some("d".to_owned()),
+                consumers: None,
+            })
+        );
+
+        let columns = first_row("<table><tr><td>a</td><td>b</td><td>c</td><td> </td></tr></table>");
+        assert_eq!(columns.and_then(|columns| columns.station), None);
+    }
+
+    #[test]
+    fn test_extract_columns_consumers() {
+        let columns = first_row(
+            "<table><tr><td>a</td><td>b</td><td>c</td><td>TS 10/0,4 KV</td><td>1.234</td></tr></table>",
+        )
+        .expect("the columns");
+        assert_eq!(columns.station.as_deref(), Some("TS 10/0,4 KV"));
+        assert_eq!(columns.consumers, Some(1234));
+
+        let columns =
+            first_row("<table><tr><td>a</td><td>b</td><td>c</td><td> 87 </td></tr></table>")
+                .expect("the columns");
+        assert_eq!(columns.station, None);
+        assert_eq!(columns.consumers, Some(87));
+    }
+
+    #[test]
+    fn test_parse_consumers_column() {
+        assert_eq!(parse_consumers_column("1.234"), Some(1234));
+        assert_eq!(parse_consumers_column("12 345"), Some(12345));
+        assert_eq!(parse_consumers_column(" 87 "), Some(87));
+        assert_eq!(parse_consumers_column(""), None);
+        assert_eq!(parse_consumers_column("n/a"), None);
+        assert_eq!(parse_consumers_column("1.234."), None);
+        assert_eq!(parse_consumers_column("99999999999"), None);
+    }
+
+    #[test]
+    fn test_find_page_date() {
+        let document = Html::parse_document(
+            "<html><body><p>Планирана искључења за датум: 2022-06-20</p></body></html>",
+        );
+        assert_eq!(
+            find_page_date(&document),
+            NaiveDate::from_ymd_opt(2022, 6, 20)
+        );
+
+        let document = Html::parse_document("<html><body><p>за 21.06.2022.</p></body></html>");
+        assert_eq!(
+            find_page_date(&document),
+            NaiveDate::from_ymd_opt(2022, 6, 21)
+        );
+
+        let document = Html::parse_document("<html><body><p>08:00-10:00</p></body></html>");
+        assert_eq!(find_page_date(&document), None);
+    }
+
+    #[test]
+    fn test_parse_time_column() {
+        assert_eq!(
+            parse_time_column(" 08:00-10:00 "),
+            Ok(vec![TimeRange::new(hm(8, 0), hm(10, 0))])
+        );
+        assert_eq!(
+            parse_time_column("08:00-10:00, 12:00-13:00"),
+            Ok(vec![
+                TimeRange::new(hm(8, 0), hm(10, 0)),
+                TimeRange::new(hm(12, 0), hm(13, 0))
+            ])
+        );
+        assert!(parse_time_column("08:00-10:00, 12:00-13:00,").is_err());
+    }
+
+    #[test]
+    fn test_parse_time_column_without_time() {
+        assert_eq!(parse_time_column(""), Ok(Vec::new()));
+        assert_eq!(parse_time_column(" - "), Ok(Vec::new()));
+        assert!(!parse_time_column("08:00-10:00")
+            .expect("parse the time")
+            .is_empty());
+        assert!(parse_time_column("--").is_err());
+    }
+}
@@ -1,6 +1,6 @@
 //! To simplify text processing and models all the input text from users and
 //! data obtained from web sites will be transliterated into Latin script and to upper case register.
-use itertools::Itertools;
+use std::borrow::Cow;
 use std::collections::HashMap;
 
 macro_rules! smap {
@@ -35,13 +35,27 @@ impl From<&'_ str> for CharOrString {
     }
 }
 
+/// The letter case of the transliterated text.
+#[derive(Eq, PartialEq, Clone, Copy, Default, Debug)]
+pub enum CasePolicy {
+    /// Everything upper case, the form the scraped data is kept in.
+    #[default]
+    Upper,
+    /// Everything lower case, for readable output.
+    Lower,
+    /// The case of the input, see [`Mapper::transliterate`].
+    Preserve,
+}
+
 #[derive(Debug)]
-pub(crate) struct Mapper {
+pub struct Mapper {
     map: HashMap<char, CharOrString>,
+    /// The upper case form of every mapping, precomputed from `map`.
+    upper: HashMap<char, String>,
 }
 
 impl Mapper {
-    pub(crate) fn new() -> Self {
+    pub fn new() -> Self {
         let mut map = HashMap::new();
 
         smap![map, 'A', 'А', 'а'];
@@ -75,23 +89,144 @@ impl Mapper {
         smap![map, "Dž", 'Џ', 'џ'];
         smap![map, 'Š', 'Ш', 'ш'];
 
-        Self { map }
+        // Macedonian letters which are absent from the Serbian alphabet.
+        // The Latin equivalents follow the Macedonian romanization:
+        // Ѓ -> Ǵ, Ќ -> Ḱ, Ѕ -> Dz, and the accented vowels Ѐ -> È, Ѝ -> Ì.
+        smap![map, 'Ǵ', 'Ѓ', 'ѓ'];
+        smap![map, 'Ḱ', 'Ќ', 'ќ'];
+        smap![map, "Dz", 'Ѕ', 'ѕ'];
+        smap![map, 'È', 'Ѐ', 'ѐ'];
+        smap![map, 'Ì', 'Ѝ', 'ѝ'];
+
+        // typographic symbols which get in the way of string matching
+        smap![map, "NO", '№'];
+        smap![map, ' ', '\u{00A0}'];
+        smap![map, '"', '„', '“', '”'];
+
+        // full-width digits which sneak in with pasted data
+        for (digit, full_width) in ('0'..='9').zip('０'..='９') {
+            smap![map, digit, full_width];
+        }
+
+        Self::from_map(map)
     }
 
-    pub(crate) fn transoform(&self, input: &str) -> String {
-        input
-            .chars()
-            .map(|c| {
-                if let Some(mapped_value) = self.map.get(&c) {
-                    match mapped_value {
-                        CharOrString::Char(rc) => rc.to_uppercase().to_string(),
-                        CharOrString::String(rs) => rs.to_uppercase(),
+    /// Creates the mapper with the extra mappings on top of the built-in
+    /// table, they take precedence over the built-in ones. The mappings
+    /// are upper-cased like the built-in ones by [`Mapper::transoform`].
+    pub fn with_overrides(extra: HashMap<char, String>) -> Self {
+        let mut map = Self::new().map;
+        for (c, mapped_value) in extra {
+            let mut chars = mapped_value.chars();
+            let mapped_value = match (chars.next(), chars.next()) {
+                (Some(rc), None) => CharOrString::Char(rc),
+                _ => CharOrString::String(mapped_value),
+            };
+            map.insert(c, mapped_value);
+        }
+        Self::from_map(map)
+    }
+
+    fn from_map(map: HashMap<char, CharOrString>) -> Self {
+        let upper = map
+            .iter()
+            .map(|(&c, mapped_value)| {
+                let upper = match mapped_value {
+                    CharOrString::Char(rc) => rc.to_uppercase().collect(),
+                    CharOrString::String(rs) => rs.to_uppercase(),
+                };
+                (c, upper)
+            })
+            .collect();
+
+        Self { map, upper }
+    }
+
+    /// Transliterates the text into upper case Latin script.
+    ///
+    /// The output is always the concatenation of
+    /// [`Mapper::transliterate_char`] applied to every input character,
+    /// so a digraph-producing letter expands in place and nothing is
+    /// reordered.
+    pub fn transoform(&self, input: &str) -> String {
+        self.transform_with(input, CasePolicy::Upper)
+    }
+
+    /// Transliterates the text into Latin script with the letter case of
+    /// the policy. [`CasePolicy::Preserve`] works as
+    /// [`Mapper::transliterate`].
+    pub fn transform_with(&self, input: &str, policy: CasePolicy) -> String {
+        let mut output = String::with_capacity(input.len());
+        match policy {
+            CasePolicy::Upper => {
+                for c in input.chars() {
+                    match self.upper.get(&c) {
+                        Some(mapped_value) => output.push_str(mapped_value),
+                        None => output.extend(c.to_uppercase()),
                     }
-                } else {
-                    c.to_uppercase().to_string()
                 }
-            })
-            .join("")
+            }
+            CasePolicy::Lower => {
+                for c in input.chars() {
+                    match self.map.get(&c) {
+                        Some(CharOrString::Char(rc)) => output.extend(rc.to_lowercase()),
+                        Some(CharOrString::String(rs)) => output.push_str(&rs.to_lowercase()),
+                        None => output.extend(c.to_lowercase()),
+                    }
+                }
+            }
+            CasePolicy::Preserve => return self.transliterate(input),
+        }
+        output
+    }
+
+    /// Returns the upper case transliteration of a single character. Mapped
+    /// characters, including the digraph-producing ones, are borrowed from
+    /// the table; any other character is upper-cased into an owned string.
+    pub fn transliterate_char(&self, c: char) -> Cow<'_, str> {
+        match self.upper.get(&c) {
+            Some(mapped_value) => Cow::Borrowed(mapped_value),
+            None => Cow::Owned(c.to_uppercase().collect()),
+        }
+    }
+
+    /// Transliterates the text keeping the letter case of the input.
+    ///
+    /// Digraphs (`Lj`, `Nj`, `Dž`) produced from an upper case letter are
+    /// written in title case unless the neighbouring letters are upper case
+    /// as well, so `Његош` becomes `Njegoš` while `ЊЕГОШ` becomes `NJEGOŠ`.
+    pub fn transliterate(&self, input: &str) -> String {
+        let mut output = String::with_capacity(input.len());
+        let mut prev: Option<char> = None;
+        let mut chars = input.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match self.map.get(&c) {
+                Some(CharOrString::Char(rc)) if c.is_lowercase() => {
+                    output.extend(rc.to_lowercase())
+                }
+                Some(CharOrString::Char(rc)) => output.push(*rc),
+                Some(CharOrString::String(rs)) if c.is_lowercase() => {
+                    output.push_str(&rs.to_lowercase())
+                }
+                Some(CharOrString::String(rs)) if c.is_uppercase() => {
+                    let all_caps = match chars.peek() {
+                        Some(next) if next.is_alphabetic() => next.is_uppercase(),
+                        _ => prev.map(char::is_uppercase).unwrap_or(false),
+                    };
+                    if all_caps {
+                        output.push_str(&rs.to_uppercase());
+                    } else {
+                        output.push_str(rs);
+                    }
+                }
+                Some(CharOrString::String(rs)) => output.push_str(rs),
+                None => output.push(c),
+            }
+            prev = Some(c);
+        }
+
+        output
     }
 }
 
@@ -101,10 +236,38 @@ impl Default for Mapper {
     }
 }
 
+/// Replaces the Serbian Latin letters with diacritics by their plain ASCII
+/// letters, e.g. `ČIKE` becomes `CIKE`, so a query typed without them still
+/// matches. `Đ` and `DJ` both become `D`, since `ĐORĐA` is typed as
+/// `DJORDJA` or `DORDA`. The result is meant for comparison only.
+pub fn fold_diacritics(input: &str) -> String {
+    let mut output = String::with_capacity(input.len());
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        let folded = match c {
+            'Č' | 'Ć' => 'C',
+            'č' | 'ć' => 'c',
+            'Š' => 'S',
+            'š' => 's',
+            'Ž' => 'Z',
+            'ž' => 'z',
+            'Đ' => 'D',
+            'đ' => 'd',
+            c => c,
+        };
+        if matches!(folded, 'D' | 'd') && matches!(chars.peek(), Some('J' | 'j')) {
+            chars.next();
+        }
+        output.push(folded);
+    }
+    output
+}
+
 #[cfg(test)]
 mod tests {
 
     use super::*;
+    use crate::addresses::{Addresses, Broj, BrojRange};
 
     #[test]
     fn test_mapper() {
@@ -123,4 +286,135 @@ mod tests {
             "U SLUŽBI GRAĐANA - ZVANIČNA PREZENTACIJA VLADE REPUBLIKE SRBIJE"
         );
     }
+
+    #[test]
+    fn test_transform_with_case_policy() {
+        let mapper = Mapper::new();
+        let sample = "Улица Његошева, ЉУБЕ ДИДИЋА № 5";
+
+        assert_eq!(
+            mapper.transform_with(sample, CasePolicy::Upper),
+            "ULICA NJEGOŠEVA, LJUBE DIDIĆA NO 5"
+        );
+        assert_eq!(
+            mapper.transform_with(sample, CasePolicy::Upper),
+            mapper.transoform(sample)
+        );
+        assert_eq!(
+            mapper.transform_with(sample, CasePolicy::Lower),
+            "ulica njegoševa, ljube didića no 5"
+        );
+        assert_eq!(
+            mapper.transform_with(sample, CasePolicy::Preserve),
+            "Ulica Njegoševa, LJUBE DIDIĆA NO 5"
+        );
+    }
+
+    #[test]
+    fn test_transliterate_char() {
+        let mapper = Mapper::new();
+
+        assert_eq!(mapper.transliterate_char('а'), "A");
+        assert_eq!(mapper.transliterate_char('љ'), "LJ");
+        assert_eq!(mapper.transliterate_char('x'), "X");
+        assert!(matches!(mapper.transliterate_char('љ'), Cow::Borrowed(_)));
+
+        let input = "Џорџа Вашингтона љубav № 12";
+        let by_char = input
+            .chars()
+            .map(|c| mapper.transliterate_char(c))
+            .collect::<String>();
+        assert_eq!(mapper.transoform(input), by_char);
+    }
+
+    #[test]
+    fn test_transliterate_digraph_case() {
+        let mapper = Mapper::new();
+
+        assert_eq!(&mapper.transliterate("Његош"), "Njegoš");
+        assert_eq!(&mapper.transliterate("ЊЕГОШ"), "NJEGOŠ");
+        assert_eq!(&mapper.transliterate("Љубљана"), "Ljubljana");
+        assert_eq!(&mapper.transliterate("КРАЉ"), "KRALJ");
+        assert_eq!(
+            &mapper.transliterate("улица Џорџа 12а"),
+            "ulica Džordža 12a"
+        );
+    }
+
+    #[test]
+    fn test_mapper_large_input() {
+        let mapper = Mapper::new();
+
+        // the straightforward implementation which allocates per character
+        let reference = |input: &str| -> String {
+            input
+                .chars()
+                .map(|c| match mapper.map.get(&c) {
+                    Some(CharOrString::Char(rc)) => rc.to_uppercase().to_string(),
+                    Some(CharOrString::String(rs)) => rs.to_uppercase(),
+                    None => c.to_uppercase().to_string(),
+                })
+                .collect()
+        };
+
+        let input = "Љубе Ненадовића: 2-18,1-17 Џорџа Вашингтона № 36а/1, ß straße ǆ ".repeat(2000);
+        assert_eq!(mapper.transoform(&input), reference(&input));
+    }
+
+    #[test]
+    fn test_mapper_macedonian_letters() {
+        let mapper = Mapper::new();
+
+        assert_eq!(&mapper.transoform("Ѓорѓе"), "ǴORǴE");
+        assert_eq!(&mapper.transoform("ѕвезда"), "DZVEZDA");
+        assert_eq!(&mapper.transoform("Ќерка Ѝ Ѐ"), "ḰERKA Ì È");
+    }
+
+    #[test]
+    fn test_mapper_typographic_symbols() {
+        let mapper = Mapper::new();
+
+        assert_eq!(&mapper.transoform("№12"), "NO12");
+        assert_eq!(&mapper.transoform("1\u{00A0}SREMSKOG"), "1 SREMSKOG");
+        assert_eq!(&mapper.transoform("„Nova“ ”21”"), "\"NOVA\" \"21\"");
+        assert_eq!(&mapper.transoform("36a/1"), "36A/1");
+    }
+
+    #[test]
+    fn test_mapper_full_width_digits() {
+        let mapper = Mapper::new();
+
+        let streets = mapper.transoform("Мала: １２３-３２１");
+        assert_eq!(&streets, "MALA: 123-321");
+        let addresses = Addresses::parse(&streets).expect("parse the normalized row");
+        assert_eq!(
+            addresses.into_iter().next().unwrap().numbers(),
+            &[Broj::from(BrojRange::from((123, 321)))]
+        );
+        assert_eq!(&mapper.transliterate("１２а"), "12a");
+    }
+
+    #[test]
+    fn test_with_overrides() {
+        let mapper = Mapper::with_overrides(HashMap::from([
+            ('ä', "ae".to_owned()),
+            ('ц', "tz".to_owned()),
+            ('ö', "o".to_owned()),
+        ]));
+
+        assert_eq!(mapper.transoform("Bäckerstraße"), "BAECKERSTRASSE");
+        assert_eq!(mapper.transoform("Улица"), "ULITZA");
+        assert_eq!(mapper.transoform("Köln"), "KOLN");
+        assert_eq!(mapper.transliterate("цар"), "tzar");
+        assert_eq!(Mapper::new().transoform("Улица"), "ULICA");
+    }
+
+    #[test]
+    fn test_fold_diacritics() {
+        assert_eq!(fold_diacritics("ČIKE MARKOVIĆA"), "CIKE MARKOVICA");
+        assert_eq!(fold_diacritics("ĐORĐA"), "DORDA");
+        assert_eq!(fold_diacritics("DJORDJA"), "DORDA");
+        assert_eq!(fold_diacritics("Šabačka žica"), "Sabacka zica");
+        assert_eq!(fold_diacritics("DŽEVDETA"), "DZEVDETA");
+    }
 }
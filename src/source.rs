@@ -0,0 +1,86 @@
+//! Where the day pages are read from: the web site or, for tests and
+//! saved pages, memory.
+use anyhow::Result as AnyhowResult;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+
+use crate::scrape;
+
+/// Provides the bodies of the day pages.
+pub trait PageSource {
+    /// Returns the body of the page, or `None` if the page is not
+    /// available and should be skipped.
+    fn fetch(&self, url: &str) -> impl Future<Output = AnyhowResult<Option<String>>> + Send;
+}
+
+/// Downloads the pages with the HTTP client.
+#[derive(Clone, Debug)]
+pub struct HttpSource {
+    client: reqwest::Client,
+    retries: u32,
+}
+
+impl HttpSource {
+    pub fn new(client: reqwest::Client, retries: u32) -> Self {
+        Self { client, retries }
+    }
+}
+
+impl PageSource for HttpSource {
+    fn fetch(&self, url: &str) -> impl Future<Output = AnyhowResult<Option<String>>> + Send {
+        scrape::fetch_with_retries(&self.client, url, self.retries)
+    }
+}
+
+/// Serves the pages from memory and records the requested urls.
+/// An unknown url is not available.
+#[derive(Default, Debug)]
+pub struct StaticSource {
+    pages: HashMap<String, String>,
+    requested: Mutex<Vec<String>>,
+}
+
+impl StaticSource {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_page(mut self, url: &str, body: &str) -> Self {
+        self.pages.insert(url.to_owned(), body.to_owned());
+        self
+    }
+
+    /// The urls requested so far, in the order of the requests.
+    pub fn requested(&self) -> Vec<String> {
+        self.requested.lock().expect("not poisoned").clone()
+    }
+}
+
+impl PageSource for StaticSource {
+    fn fetch(&self, url: &str) -> impl Future<Output = AnyhowResult<Option<String>>> + Send {
+        self.requested
+            .lock()
+            .expect("not poisoned")
+            .push(url.to_owned());
+        std::future::ready(Ok(self.pages.get(url).cloned()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_static_source() {
+        let source = StaticSource::new().with_page("day0", "<html></html>");
+
+        assert_eq!(
+            source.fetch("day0").await.unwrap().as_deref(),
+            Some("<html></html>")
+        );
+        assert_eq!(source.fetch("day1").await.unwrap(), None);
+        assert_eq!(source.requested(), vec!["day0", "day1"]);
+    }
+}
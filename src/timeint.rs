@@ -1,25 +1,80 @@
-use nom::IResult;
-use nom::bytes::complete::tag;
-use nom::character::complete::digit1;
-use nom::sequence::separated_pair;
 use chrono::NaiveTime;
-use nom::combinator::{map, map_res};
-use nom::{Err, error::Error};
-
+use nom::branch::alt;
+use nom::bytes::complete::{tag, tag_no_case};
+use nom::character::complete::{digit1, multispace0, multispace1};
+use nom::combinator::{all_consuming, map, map_opt, map_res, opt};
+use nom::multi::separated_list1;
+use nom::sequence::{delimited, pair, preceded, separated_pair, terminated};
+use nom::IResult;
+use nom::{error::Error, Err};
+use std::fmt;
+use std::str::FromStr;
 
-#[derive(Eq, PartialEq, Debug)]
-pub(crate) struct TimeRange {
+/// A time interval of a day, written as `08:30-14:00`. With the `serde`
+/// feature it is serialized in the same compact form.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct TimeRange {
     from: NaiveTime,
     to: NaiveTime,
 }
 
 impl TimeRange {
-    pub(crate) fn new(from: NaiveTime, to: NaiveTime) -> Self {
+    pub fn new(from: NaiveTime, to: NaiveTime) -> Self {
         Self { from, to }
     }
 
-    pub(crate) fn parse(input: &str) -> Result<Self, Err<Error<&str>>> {
-        let (_, result) = parse_interval(input)?;
+    pub fn start(&self) -> NaiveTime {
+        self.from
+    }
+
+    pub fn end(&self) -> NaiveTime {
+        self.to
+    }
+
+    /// Tells whether the range ends on the next day, like `22:00-06:00`.
+    pub fn is_overnight(&self) -> bool {
+        self.to <= self.from
+    }
+
+    /// Tells whether the time falls into the range, the end excluded. An
+    /// overnight range holds the evening and the early morning times.
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.is_overnight() {
+            self.from <= time || time < self.to
+        } else {
+            self.from <= time && time < self.to
+        }
+    }
+
+    /// Splits an overnight range at midnight into the evening and the
+    /// morning part, so `22:00-06:00` gives `22:00-00:00` and
+    /// `00:00-06:00`. A range ending at midnight, as `22:00-24:00`, belongs
+    /// to a single day and is returned as is, as is a same-day range.
+    pub fn split_at_midnight(&self) -> Vec<Self> {
+        if self.is_overnight() && self.to != NaiveTime::MIN {
+            vec![
+                Self::new(self.from, NaiveTime::MIN),
+                Self::new(NaiveTime::MIN, self.to),
+            ]
+        } else {
+            vec![self.clone()]
+        }
+    }
+
+    /// Parses a time range like `08:30-14:00`. Hours without minutes, as
+    /// `8h-10h` or `od 8 do 10`, are accepted as well, and `24:00` is the
+    /// midnight ending the day. The whole input has to be a single range.
+    pub fn parse(input: &str) -> Result<Self, TimeParseError> {
+        let (_, result) = all_consuming(parse_interval)(input)?;
+        Ok(result)
+    }
+
+    /// Parses the comma separated time ranges of a cell which lists
+    /// several windows, like `08:00-10:00, 13:00-15:00`. A single range is
+    /// a list of one.
+    pub fn parse_list(input: &str) -> Result<Vec<Self>, TimeParseError> {
+        let separator = delimited(multispace0, tag(","), multispace0);
+        let (_, result) = all_consuming(separated_list1(separator, parse_interval))(input)?;
         Ok(result)
     }
 }
@@ -30,16 +85,123 @@ impl From<(NaiveTime, NaiveTime)> for TimeRange {
     }
 }
 
+impl fmt::Display for TimeRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}-{}",
+            self.from.format("%H:%M"),
+            self.to.format("%H:%M")
+        )
+    }
+}
+
+impl FromStr for TimeRange {
+    type Err = TimeParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for TimeRange {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for TimeRange {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+        text.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// The error returned when a time range cannot be recognized.
+/// It keeps the part of the input the parser failed at.
+#[derive(Eq, PartialEq, Debug)]
+pub struct TimeParseError {
+    fragment: String,
+}
+
+impl TimeParseError {
+    pub fn fragment(&self) -> &str {
+        &self.fragment
+    }
+}
+
+impl fmt::Display for TimeParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "cannot parse time range at \"{}\"", self.fragment)
+    }
+}
+
+impl std::error::Error for TimeParseError {}
+
+impl From<Err<Error<&str>>> for TimeParseError {
+    fn from(err: Err<Error<&str>>) -> Self {
+        let fragment = match err {
+            Err::Error(e) | Err::Failure(e) => e.input.to_owned(),
+            Err::Incomplete(_) => String::new(),
+        };
+        Self { fragment }
+    }
+}
+
 fn digit_parse(input: &str) -> IResult<&str, u32> {
     map_res(digit1, str::parse::<u32>)(input)
 }
 
+/// The time of the day, `24:00` being the midnight ending the day.
+fn clock_time(hh: u32, mm: u32) -> Option<NaiveTime> {
+    match (hh, mm) {
+        (24, 0) => Some(NaiveTime::MIN),
+        (hh, mm) => NaiveTime::from_hms_opt(hh, mm, 0),
+    }
+}
+
 fn parse_time(input: &str) -> IResult<&str, NaiveTime> {
-    map(separated_pair(digit_parse, tag(":"), digit_parse), |(hh, mm)| NaiveTime::from_hms(hh, mm, 0))(input)
+    map_opt(
+        separated_pair(digit_parse, tag(":"), digit_parse),
+        |(hh, mm)| clock_time(hh, mm),
+    )(input)
 }
 
+/// Parses an hour given without minutes, as `8`, `8h` or `8č`.
+fn parse_hour(input: &str) -> IResult<&str, NaiveTime> {
+    let suffix = alt((tag_no_case("h"), tag_no_case("č"), tag_no_case("ч")));
+    map_opt(terminated(digit_parse, opt(suffix)), |hh| clock_time(hh, 0))(input)
+}
+
+fn parse_point(input: &str) -> IResult<&str, NaiveTime> {
+    alt((parse_time, parse_hour))(input)
+}
+
+/// Parses the `od 8 do 10` (from 8 to 10) phrasing.
+fn parse_od_do(input: &str) -> IResult<&str, TimeRange> {
+    let od = pair(alt((tag_no_case("od"), tag_no_case("од"))), multispace1);
+    let do_ = delimited(
+        multispace1,
+        alt((tag_no_case("do"), tag_no_case("до"))),
+        multispace1,
+    );
+    map(
+        preceded(od, separated_pair(parse_point, do_, parse_point)),
+        TimeRange::from,
+    )(input)
+}
+
+/// Parses an interval as `08:30-14:00`, `8h-10h` or `od 8 do 10`.
 fn parse_interval(input: &str) -> IResult<&str, TimeRange> {
-    map(separated_pair(parse_time, tag("-"), parse_time), TimeRange::from)(input)
+    alt((
+        map(
+            separated_pair(parse_point, tag("-"), parse_point),
+            TimeRange::from,
+        ),
+        parse_od_do,
+    ))(input)
 }
 
 #[cfg(test)]
@@ -47,16 +209,139 @@ mod tests {
 
     use super::*;
 
+    fn hm(hh: u32, mm: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hh, mm, 0).expect("valid time")
+    }
 
     #[test]
     fn test_parse_time() {
         let (_, time) = parse_time("12:00").expect("can parse time");
-        assert_eq!(time, NaiveTime::from_hms(12, 00, 00));
+        assert_eq!(time, hm(12, 00));
     }
 
     #[test]
     fn test_parse_interval() {
         let time_range = TimeRange::parse("12:00-13:15").expect("can parse time interval");
-        assert_eq!(time_range, TimeRange::new(NaiveTime::from_hms(12, 00, 00), NaiveTime::from_hms(13, 15, 00)))
+        assert_eq!(time_range, TimeRange::new(hm(12, 00), hm(13, 15)))
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let time_range = TimeRange::new(hm(8, 30), hm(15, 0));
+        let json = serde_json::to_string(&time_range).expect("serialize time range");
+        let restored: TimeRange = serde_json::from_str(&json).expect("deserialize time range");
+        assert_eq!(restored, time_range);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_compact_string() {
+        let time_range = TimeRange::new(hm(22, 0), hm(6, 0));
+        let json = serde_json::to_string(&time_range).expect("serialize time range");
+        assert_eq!(json, "\"22:00-06:00\"");
+        let restored: TimeRange = serde_json::from_str(&json).expect("deserialize time range");
+        assert_eq!(restored, time_range);
+        assert!(restored.is_overnight());
+
+        assert!(serde_json::from_str::<TimeRange>("\"22:00\"").is_err());
+    }
+
+    #[test]
+    fn test_contains() {
+        let range = TimeRange::new(hm(8, 30), hm(14, 0));
+        assert!(range.contains(hm(8, 30)));
+        assert!(range.contains(hm(13, 59)));
+        assert!(!range.contains(hm(14, 0)));
+        assert!(!range.contains(hm(7, 0)));
+
+        let overnight = TimeRange::new(hm(22, 0), hm(6, 0));
+        assert!(overnight.contains(hm(23, 0)));
+        assert!(overnight.contains(hm(3, 0)));
+        assert!(!overnight.contains(hm(6, 0)));
+        assert!(!overnight.contains(hm(12, 0)));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_split_at_midnight() {
+        let overnight = TimeRange::new(hm(22, 0), hm(6, 0));
+        assert_eq!(
+            overnight.split_at_midnight(),
+            vec![
+                TimeRange::new(hm(22, 0), hm(0, 0)),
+                TimeRange::new(hm(0, 0), hm(6, 0))
+            ]
+        );
+
+        let same_day = TimeRange::new(hm(8, 30), hm(14, 0));
+        assert_eq!(same_day.split_at_midnight(), vec![same_day.clone()]);
+
+        // 24:00 is the midnight ending the day
+        let until_midnight = TimeRange::parse("22:00-24:00").expect("parse the range");
+        assert_eq!(until_midnight, TimeRange::new(hm(22, 0), hm(0, 0)));
+        assert_eq!(
+            until_midnight.split_at_midnight(),
+            vec![until_midnight.clone()]
+        );
+        assert!(TimeRange::parse("22:00-24:30").is_err());
+    }
+
+    #[test]
+    fn test_display_and_from_str() {
+        let time_range: TimeRange = "08:05-14:00".parse().expect("parse the range");
+        assert_eq!(time_range, TimeRange::new(hm(8, 5), hm(14, 0)));
+        assert_eq!(time_range.to_string(), "08:05-14:00");
+    }
+
+    #[test]
+    fn test_parse_hours_only() {
+        let expected = TimeRange::new(hm(8, 0), hm(10, 0));
+        assert_eq!(TimeRange::parse("8h-10h"), Ok(expected.clone()));
+        assert_eq!(TimeRange::parse("od 8 do 10"), Ok(expected.clone()));
+        assert_eq!(TimeRange::parse("8č-10č"), Ok(expected.clone()));
+        assert_eq!(
+            TimeRange::parse("OD 8 DO 10:30"),
+            Ok(TimeRange::new(hm(8, 0), hm(10, 30)))
+        );
+        assert_eq!(TimeRange::parse("од 8 до 10"), Ok(expected));
+        assert!(TimeRange::parse("8h-25h").is_err());
+        assert!(TimeRange::parse("od 8").is_err());
+    }
+
+    #[test]
+    fn test_parse_list() {
+        assert_eq!(
+            TimeRange::parse_list("08:00-10:00,13:00-15:00"),
+            Ok(vec![
+                TimeRange::new(hm(8, 0), hm(10, 0)),
+                TimeRange::new(hm(13, 0), hm(15, 0))
+            ])
+        );
+        assert_eq!(
+            TimeRange::parse_list("8h-10h , od 13 do 15"),
+            Ok(vec![
+                TimeRange::new(hm(8, 0), hm(10, 0)),
+                TimeRange::new(hm(13, 0), hm(15, 0))
+            ])
+        );
+        assert_eq!(
+            TimeRange::parse_list("08:30-14:00"),
+            Ok(vec![TimeRange::new(hm(8, 30), hm(14, 0))])
+        );
+        let err = TimeRange::parse_list("08:00-10:00,").expect_err("rejects a trailing comma");
+        assert_eq!(err.fragment(), ",");
+    }
+
+    #[test]
+    fn test_parse_rejects_trailing_input() {
+        let err = TimeRange::parse("08:00-10:00,12:00-13:00").expect_err("rejects a list");
+        assert_eq!(err.fragment(), ",12:00-13:00");
+    }
+
+    #[test]
+    fn test_parse_error_mentions_fragment() {
+        let err = TimeRange::parse("notatime").expect_err("rejects garbage");
+        assert_eq!(err.fragment(), "notatime");
+        assert!(err.to_string().contains("notatime"));
+    }
+}
@@ -0,0 +1,136 @@
+//! Consolidation of the outages of all the day pages into a single view
+//! with an entry per street and set of house numbers.
+use itertools::Itertools;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+
+use crate::scrape::Outage;
+use crate::timeint::TimeRange;
+
+/// A day the numbers of a street are out of power, `day` counts the days
+/// from today and `time_ranges` are the windows of that day.
+#[derive(Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Occurrence {
+    pub day: usize,
+    pub time_ranges: Vec<TimeRange>,
+}
+
+impl fmt::Display for Occurrence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.time_ranges.is_empty() {
+            write!(f, "day {}", self.day)
+        } else {
+            write!(f, "day {} {}", self.day, self.time_ranges.iter().join(", "))
+        }
+    }
+}
+
+/// The house numbers of a street along with every day they are affected.
+/// `numbers` are written as listed on the page, e.g. `2-10` or `36A/1`.
+#[derive(Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WeeklyOutage {
+    pub street: String,
+    pub numbers: Vec<String>,
+    pub occurrences: Vec<Occurrence>,
+}
+
+/// Collects the outages of all the days, a street listing the same set of
+/// numbers on several days becomes a single entry with an occurrence per
+/// day. The entries keep the order they are first seen in, outages whose
+/// addresses cannot be parsed are left out.
+pub fn aggregate<'a, I>(outages: I) -> Vec<WeeklyOutage>
+where
+    I: IntoIterator<Item = (usize, &'a Outage)>,
+{
+    let mut weekly: Vec<WeeklyOutage> = Vec::new();
+    let mut index: HashMap<(String, BTreeSet<String>), usize> = HashMap::new();
+
+    for (day, outage) in outages {
+        let addresses = match outage.addresses() {
+            Ok(addresses) => addresses,
+            Err(_) => continue,
+        };
+
+        for record in &addresses {
+            let numbers = record
+                .numbers()
+                .iter()
+                .map(|broj| broj.to_string())
+                .collect_vec();
+            let key = (
+                record.street().to_owned(),
+                numbers.iter().cloned().collect::<BTreeSet<_>>(),
+            );
+            let occurrence = Occurrence {
+                day,
+                time_ranges: outage.time_ranges.clone(),
+            };
+
+            match index.get(&key) {
+                Some(&i) => {
+                    if !weekly[i].occurrences.contains(&occurrence) {
+                        weekly[i].occurrences.push(occurrence);
+                    }
+                }
+                None => {
+                    index.insert(key, weekly.len());
+                    weekly.push(WeeklyOutage {
+                        street: record.street().to_owned(),
+                        numbers,
+                        occurrences: vec![occurrence],
+                    });
+                }
+            }
+        }
+    }
+
+    weekly
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn outage(time: &str, streets: &str) -> Outage {
+        Outage {
+            time: time.to_owned(),
+            time_ranges: TimeRange::parse_list(time).unwrap_or_default(),
+            streets: streets.to_owned(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_aggregate() {
+        let day0 = outage("08:30-14:00", "MALA: 2-10,1,  BRODSKA: 2-18,");
+        let day1 = outage("09:00-12:00", "PILOTSKA: BB,");
+        let day2 = outage("22:00-06:00", "MALA: 1,2-10,  BRODSKA: 2-20,");
+
+        let weekly = aggregate([(0, &day0), (1, &day1), (2, &day2)]);
+        assert_eq!(weekly.len(), 4);
+
+        assert_eq!(weekly[0].street, "MALA");
+        assert_eq!(weekly[0].numbers, vec!["2-10", "1"]);
+        assert_eq!(
+            weekly[0].occurrences,
+            vec![
+                Occurrence {
+                    day: 0,
+                    time_ranges: TimeRange::parse_list("08:30-14:00").unwrap(),
+                },
+                Occurrence {
+                    day: 2,
+                    time_ranges: TimeRange::parse_list("22:00-06:00").unwrap(),
+                },
+            ]
+        );
+        assert_eq!(weekly[1].street, "BRODSKA");
+        assert_eq!(weekly[1].occurrences.len(), 1);
+        assert_eq!(weekly[3].street, "BRODSKA");
+        assert_eq!(weekly[3].numbers, vec!["2-20"]);
+        assert_eq!(weekly[0].occurrences[1].to_string(), "day 2 22:00-06:00");
+    }
+}
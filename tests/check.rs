@@ -0,0 +1,40 @@
+use beobot::check;
+use beobot::job::ScrapeJob;
+use beobot::scrape::PageParser;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[tokio::test]
+async fn test_check_pages_reports_failures() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/Dan_0_Iskljucenja.htm"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_string(include_str!("data/beograd_day0.html")),
+        )
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/Dan_1_Iskljucenja.htm"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_string("<html><body><p>Nema</p></body></html>"),
+        )
+        .mount(&server)
+        .await;
+
+    let day_pages = ScrapeJob::builder()
+        .base_url(&server.uri())
+        .day_pages()
+        .expect("the day pages");
+    let page_parser = PageParser::new().expect("build the page parser");
+    let checks = check::check_pages(&reqwest::Client::new(), &page_parser, &day_pages).await;
+
+    assert_eq!(checks.len(), day_pages.len());
+    assert!(checks[0].passed());
+    assert_eq!(checks[0].to_string(), format!("PASS {}", day_pages[0].url));
+    assert!(!checks[1].passed());
+    assert!(checks[1].to_string().starts_with("FAIL "));
+    assert!(checks[1].to_string().contains("data table"));
+    // the rest of the pages are not mounted
+    assert_eq!(checks[2].failure.as_deref(), Some("status 404"));
+}
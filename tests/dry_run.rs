@@ -0,0 +1,95 @@
+use beobot::cli::Cli;
+use beobot::output;
+use clap::Parser;
+
+/// Lists the urls the way `main` does for `--dry-run`.
+fn dry_run(args: &[&str]) -> String {
+    let cli = Cli::try_parse_from(args).expect("parse the arguments");
+    assert!(cli.dry_run);
+
+    let job = cli
+        .scrape_job(cli.config().expect("the configuration"))
+        .expect("the scrape job");
+    let mut buffer = Vec::new();
+    output::write_urls(&mut buffer, &job.day_pages().expect("the day pages"))
+        .expect("write the urls");
+    String::from_utf8(buffer).expect("utf-8 output")
+}
+
+#[test]
+fn test_dry_run_lists_the_selected_url() {
+    let base_url = "http://127.0.0.1:8080";
+    let text = dry_run(&[
+        "beobot",
+        "--dry-run",
+        "--city",
+        "novi-sad",
+        "--day",
+        "0",
+        "--base-url",
+        base_url,
+    ]);
+    assert_eq!(text, format!("{base_url}/NoviSad_Dan_0_Iskljucenja.htm\n"));
+
+    // `--now` is about today's page only
+    let text = dry_run(&["beobot", "--dry-run", "--now", "--base-url", base_url]);
+    assert_eq!(text, format!("{base_url}/Dan_0_Iskljucenja.htm\n"));
+
+    let text = dry_run(&["beobot", "--dry-run", "--days-ahead", "2"]);
+    assert_eq!(
+        text,
+        "https://elektrodistribucija.rs/Dan_0_Iskljucenja.htm\n\
+         https://elektrodistribucija.rs/Dan_1_Iskljucenja.htm\n"
+    );
+
+    // a day past the days ahead is refused rather than listed
+    let cli = Cli::try_parse_from(["beobot", "--dry-run", "--day", "5"]).expect("parse");
+    assert!(cli
+        .scrape_job(cli.config().expect("the configuration"))
+        .expect("the scrape job")
+        .day_pages()
+        .is_err());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_dry_run_lists_a_city_of_the_config_file() {
+    let dir = std::env::temp_dir().join(format!("beobot-config-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).expect("create the directory");
+    let path = dir.join("beobot.toml");
+    std::fs::write(
+        &path,
+        "[cities.nis]\nday_url = \"{base_url}/Nis_Dan_{day}_Iskljucenja.htm\"\n",
+    )
+    .expect("write the configuration");
+    let config = path.to_str().expect("a utf-8 path");
+
+    let text = dry_run(&[
+        "beobot",
+        "--dry-run",
+        "--config",
+        config,
+        "--city",
+        "nis",
+        "--days-ahead",
+        "2",
+        "--base-url",
+        "http://127.0.0.1:8080",
+    ]);
+    assert_eq!(
+        text,
+        "http://127.0.0.1:8080/Nis_Dan_0_Iskljucenja.htm\n\
+         http://127.0.0.1:8080/Nis_Dan_1_Iskljucenja.htm\n"
+    );
+
+    // without the file the city is not known
+    let cli = Cli::try_parse_from(["beobot", "--dry-run", "--city", "nis"]).expect("parse");
+    let err = cli
+        .scrape_job(cli.config().expect("the configuration"))
+        .expect("the scrape job")
+        .day_pages()
+        .expect_err("the city is not configured");
+    assert_eq!(err.to_string(), "the city nis is not configured");
+
+    std::fs::remove_dir_all(&dir).expect("remove the directory");
+}
@@ -0,0 +1,238 @@
+use beobot::job::ScrapeJob;
+use beobot::logging;
+use beobot::scrape::{self, ClientConfig, PageParser, ParsedPage};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+use wiremock::matchers::{header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+mod common;
+
+use common::CapturedLogs;
+
+#[tokio::test]
+async fn test_fetch_skips_error_pages() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/Dan_0_Iskljucenja.htm"))
+        .respond_with(ResponseTemplate::new(404).set_body_string("<html>Not Found</html>"))
+        .mount(&server)
+        .await;
+
+    let logs = CapturedLogs::default();
+    let _guard = tracing::subscriber::set_default(logs.subscriber());
+
+    let url = format!("{}/Dan_0_Iskljucenja.htm", server.uri());
+    let body = scrape::fetch(&reqwest::Client::new(), &url)
+        .await
+        .expect("an error status is not a fetch failure");
+
+    assert_eq!(body, None);
+    assert!(logs
+        .contents()
+        .lines()
+        .any(|line| line.contains("ERROR") && line.contains("404 Not Found")));
+}
+
+#[tokio::test]
+async fn test_quiet_logs_only_failures() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/Dan_0_Iskljucenja.htm"))
+        .respond_with(ResponseTemplate::new(404).set_body_string("<html>Not Found</html>"))
+        .mount(&server)
+        .await;
+
+    let logs = CapturedLogs::default();
+    let _guard = tracing::subscriber::set_default(
+        logs.subscriber_with_filter(logging::log_filter(Some("debug"), true)),
+    );
+
+    PageParser::new()
+        .expect("build the page parser")
+        .parse_page(include_str!("data/malformed_rows.html"))
+        .expect("parse the page with broken rows");
+    let url = format!("{}/Dan_0_Iskljucenja.htm", server.uri());
+    scrape::fetch(&reqwest::Client::new(), &url)
+        .await
+        .expect("an error status is not a fetch failure");
+
+    let contents = logs.contents();
+    assert!(!contents.contains("malformed row"));
+    assert!(contents
+        .lines()
+        .any(|line| line.contains("ERROR") && line.contains("404 Not Found")));
+}
+
+#[tokio::test]
+async fn test_fetch_returns_body() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/Dan_0_Iskljucenja.htm"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("<html></html>"))
+        .mount(&server)
+        .await;
+
+    let url = format!("{}/Dan_0_Iskljucenja.htm", server.uri());
+    let body = scrape::fetch(&reqwest::Client::new(), &url)
+        .await
+        .expect("fetch the page");
+
+    assert_eq!(body.as_deref(), Some("<html></html>"));
+}
+
+#[tokio::test]
+async fn test_fetch_page_follows_redirects() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/Iskljucenja.htm"))
+        .respond_with(
+            ResponseTemplate::new(302).insert_header("location", "/Dan_0_Iskljucenja.htm"),
+        )
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/Dan_0_Iskljucenja.htm"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_string("<html>Бродска</html>")
+                .insert_header("content-type", "text/html; charset=utf-8"),
+        )
+        .mount(&server)
+        .await;
+
+    let url = format!("{}/Iskljucenja.htm", server.uri());
+    let page = scrape::fetch_page(&reqwest::Client::new(), &url)
+        .await
+        .expect("fetch the page");
+
+    assert_eq!(page.status, 200);
+    assert_eq!(
+        page.final_url,
+        format!("{}/Dan_0_Iskljucenja.htm", server.uri())
+    );
+    assert_eq!(page.body, "<html>Бродска</html>");
+}
+
+#[tokio::test]
+async fn test_fetch_page_keeps_error_pages() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/Dan_0_Iskljucenja.htm"))
+        .respond_with(ResponseTemplate::new(404).set_body_string("<html>Not Found</html>"))
+        .mount(&server)
+        .await;
+
+    let url = format!("{}/Dan_0_Iskljucenja.htm", server.uri());
+    let page = scrape::fetch_page(&reqwest::Client::new(), &url)
+        .await
+        .expect("an error status is not a fetch failure");
+
+    assert_eq!(page.status, 404);
+    assert_eq!(page.final_url, url);
+    assert_eq!(page.body, "<html>Not Found</html>");
+}
+
+#[tokio::test]
+async fn test_scrape_job_keeps_pages_parsed_before_cancel() {
+    let server = MockServer::start().await;
+    let page = include_str!("data/beograd_day0.html");
+    Mock::given(method("GET"))
+        .and(path("/Dan_0_Iskljucenja.htm"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(page))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/Dan_1_Iskljucenja.htm"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_string(page)
+                .set_delay(Duration::from_secs(30)),
+        )
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/Dan_2_Iskljucenja.htm"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(page))
+        .expect(0)
+        .mount(&server)
+        .await;
+
+    // cancel while the second page is still being fetched
+    let cancel = CancellationToken::new();
+    tokio::spawn({
+        let cancel = cancel.clone();
+        async move {
+            tokio::time::sleep(Duration::from_millis(500)).await;
+            cancel.cancel();
+        }
+    });
+
+    let days = Arc::new(Mutex::new(Vec::new()));
+    let job = ScrapeJob::builder()
+        .base_url(&server.uri())
+        .client(reqwest::Client::new())
+        .retries(0)
+        .cancellation(cancel)
+        .output({
+            let days = Arc::clone(&days);
+            move |day, _: &ParsedPage| {
+                days.lock().expect("not poisoned").push(day);
+                Ok(())
+            }
+        })
+        .build()
+        .expect("build the job");
+    let outages = tokio::time::timeout(Duration::from_secs(10), job.run())
+        .await
+        .expect("cancelling stops the scraping")
+        .expect("run the job");
+
+    assert_eq!(*days.lock().expect("not poisoned"), vec![0]);
+    assert!(!outages.is_empty());
+}
+
+#[tokio::test]
+async fn test_build_client_sends_user_agent() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/Dan_0_Iskljucenja.htm"))
+        .and(header("user-agent", "beobot-test"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("<html></html>"))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let cfg = ClientConfig {
+        user_agent: "beobot-test".to_owned(),
+        ..Default::default()
+    };
+    let client = scrape::build_client(&cfg).expect("build the client");
+
+    let url = format!("{}/Dan_0_Iskljucenja.htm", server.uri());
+    let body = scrape::fetch(&client, &url).await.expect("fetch the page");
+    assert_eq!(body.as_deref(), Some("<html></html>"));
+}
+
+#[tokio::test]
+async fn test_fetch_retries_timed_out_requests() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/Dan_0_Iskljucenja.htm"))
+        .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(5)))
+        .expect(2)
+        .mount(&server)
+        .await;
+
+    let cfg = ClientConfig {
+        timeout: Duration::from_millis(100),
+        retries: 1,
+        ..Default::default()
+    };
+    let client = scrape::build_client(&cfg).expect("build the client");
+
+    let url = format!("{}/Dan_0_Iskljucenja.htm", server.uri());
+    let res = scrape::fetch_with_retries(&client, &url, cfg.retries).await;
+    assert!(res.is_err());
+}
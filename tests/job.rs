@@ -0,0 +1,151 @@
+use beobot::filter::OutageFilter;
+use beobot::job::ScrapeJob;
+use beobot::source::StaticSource;
+use std::sync::{Arc, Mutex};
+use tokio_util::sync::CancellationToken;
+
+static BEOGRAD_DAY_0: &str = include_str!("data/beograd_day0.html");
+
+static WITH_STATION: &str = include_str!("data/with_station.html");
+
+static UNPARSABLE_STREETS: &str = include_str!("data/unparsable_streets.html");
+
+#[tokio::test]
+async fn test_scrape_job_against_static_source() {
+    let builder = ScrapeJob::builder()
+        .base_url("http://localhost")
+        .days(2)
+        .filter(OutageFilter::new(Some("batajnički drum"), Some(269)));
+    let day_pages = builder.day_pages().expect("the day pages");
+    assert_eq!(day_pages[1].url, "http://localhost/Dan_1_Iskljucenja.htm");
+    let source = StaticSource::new()
+        .with_page(&day_pages[0].url, BEOGRAD_DAY_0)
+        .with_page(&day_pages[1].url, WITH_STATION);
+
+    let job = builder.build_with(source).expect("build the job");
+    assert_eq!(job.day_pages(), day_pages);
+
+    let outages = job.run().await.expect("run the job");
+    // the street is on both days, on the second one with the station
+    assert_eq!(outages.len(), 2);
+    assert!(outages.iter().all(|outage| outage.municipality == "ZEMUN"));
+    assert_eq!(outages[0].station, None);
+    assert!(outages[1].station.is_some());
+}
+
+#[test]
+fn test_scrape_job_rejects_unknown_city() {
+    let err = ScrapeJob::builder()
+        .city("kragujevac")
+        .build_with(StaticSource::new())
+        .err()
+        .expect("the city is not configured");
+    assert_eq!(err.to_string(), "the city kragujevac is not configured");
+
+    assert!(ScrapeJob::builder()
+        .days(2)
+        .day(Some(3))
+        .build_with(StaticSource::new())
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_strict_job_tells_where_the_streets_fail() {
+    let builder = ScrapeJob::builder().base_url("http://localhost").days(2);
+    let day_pages = builder.day_pages().expect("the day pages");
+    let source = || {
+        StaticSource::new()
+            .with_page(&day_pages[0].url, BEOGRAD_DAY_0)
+            .with_page(&day_pages[1].url, UNPARSABLE_STREETS)
+    };
+
+    let error = ScrapeJob::builder()
+        .base_url("http://localhost")
+        .days(2)
+        .strict(true)
+        .build_with(source())
+        .expect("build the job")
+        .run()
+        .await
+        .expect_err("the streets of the second row cannot be parsed");
+    let message = format!("{error:#}");
+    assert!(
+        message.starts_with("day 1 (http://localhost/Dan_1_Iskljucenja.htm): "),
+        "{message}"
+    );
+    assert!(
+        message.contains("row #1: cannot parse the streets `NEMA PODATAKA`"),
+        "{message}"
+    );
+    assert!(message.contains("Чукарица | 10:00-12:00"), "{message}");
+
+    // the row is skipped otherwise
+    let outages = builder
+        .build_with(source())
+        .expect("build the job")
+        .run()
+        .await
+        .expect("run the job");
+    assert!(outages
+        .iter()
+        .all(|outage| outage.municipality != "ČUKARICA"));
+}
+
+#[tokio::test]
+async fn test_scrape_job_output_and_cancellation() {
+    let builder = ScrapeJob::builder().base_url("http://localhost").days(2);
+    let day_pages = builder.day_pages().expect("the day pages");
+    let source = StaticSource::new()
+        .with_page(&day_pages[0].url, BEOGRAD_DAY_0)
+        .with_page(&day_pages[1].url, WITH_STATION);
+
+    // every page is handed over with its outages filtered
+    let pages = Arc::new(Mutex::new(Vec::new()));
+    let job = builder
+        .filter(OutageFilter::new(Some("batajnički drum"), None))
+        .output({
+            let pages = pages.clone();
+            move |day: usize, page: &beobot::scrape::ParsedPage| {
+                pages.lock().unwrap().push((day, page.outages.len()));
+                Ok(())
+            }
+        })
+        .build_with(source)
+        .expect("build the job");
+    let outages = job.run().await.expect("run the job");
+    let pages = pages.lock().unwrap().clone();
+    assert_eq!(
+        pages.iter().map(|(day, _)| *day).collect::<Vec<_>>(),
+        [0, 1]
+    );
+    assert_eq!(pages.iter().map(|(_, n)| n).sum::<usize>(), outages.len());
+
+    // a cancelled job fetches nothing
+    let cancel = CancellationToken::new();
+    cancel.cancel();
+    let job = ScrapeJob::builder()
+        .base_url("http://localhost")
+        .cancellation(cancel)
+        .build_with(StaticSource::new().with_page(&day_pages[0].url, BEOGRAD_DAY_0))
+        .expect("build the job");
+    assert_eq!(job.run().await.expect("run the job"), vec![]);
+}
+
+#[tokio::test]
+async fn test_scrape_job_over_saved_pages() {
+    let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data/batch");
+    let job = |strict| {
+        ScrapeJob::builder()
+            .files(vec![dir.clone()])
+            .strict(strict)
+            .build_with(StaticSource::new())
+            .expect("build the job")
+    };
+
+    // the last page has no outages table, it is skipped
+    let outages = job(false).run().await.expect("run the job");
+    assert_eq!(outages.len(), 4);
+
+    let error = job(true).run().await.expect_err("a file failed");
+    assert_eq!(error.to_string(), "1 of 3 files failed");
+}
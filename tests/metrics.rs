@@ -0,0 +1,51 @@
+use beobot::metrics::{self, DayMetrics};
+use beobot::scrape::PageParser;
+
+#[test]
+fn test_metrics_file() {
+    let page_parser = PageParser::new().expect("build the page parser");
+    let page = page_parser
+        .parse_page(include_str!("data/malformed_rows.html"))
+        .expect("parse the saved day page");
+
+    let days = [
+        DayMetrics {
+            day: 0,
+            fetched: true,
+            stats: page.stats,
+            outages: page.outages.len(),
+        },
+        DayMetrics::new(1),
+    ];
+
+    let dir = std::env::temp_dir().join(format!("beobot-metrics-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).expect("create the directory");
+    let path = dir.join("beobot.prom");
+    std::fs::write(&path, "stale").expect("write the old file");
+
+    metrics::write_atomically(&path, &metrics::render_metrics("beograd", &days))
+        .expect("write the metrics");
+    let contents = std::fs::read_to_string(&path).expect("read the metrics");
+    std::fs::remove_dir_all(&dir).expect("remove the directory");
+
+    let lines = contents.lines().collect::<Vec<_>>();
+    assert!(lines.contains(&"# TYPE beobot_pages_fetched gauge"));
+    assert!(lines.contains(&"beobot_pages_fetched{city=\"beograd\",day=\"0\"} 1"));
+    assert!(lines.contains(&"beobot_pages_fetched{city=\"beograd\",day=\"1\"} 0"));
+    assert!(lines.contains(
+        &format!(
+            "beobot_rows_parsed{{city=\"beograd\",day=\"0\"}} {}",
+            page.stats.parsed
+        )
+        .as_str()
+    ));
+    assert!(lines.contains(
+        &format!(
+            "beobot_rows_malformed{{city=\"beograd\",day=\"0\"}} {}",
+            page.stats.malformed
+        )
+        .as_str()
+    ));
+    assert!(lines.contains(&"beobot_outages_total{city=\"beograd\",day=\"1\"} 0"));
+    assert!(page.stats.malformed > 0);
+}
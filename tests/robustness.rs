@@ -0,0 +1,136 @@
+//! The parsers get whatever the site publishes, so they have to return a
+//! value or an error for any input, and never panic or hang.
+use beobot::addresses::Addresses;
+use beobot::script_mapper::Mapper;
+use beobot::timeint::TimeRange;
+use proptest::prelude::*;
+
+/// Rows of the kind published on the site.
+const REAL_ROWS: &[&str] = &[
+    "BATAJNIČKI DRUM: BB,261-265,269,283-293,299,303-303A,  AUTOPUT ZA NOVI SAD: BB,284,294-296F,",
+    "NASELJE BATAJNICA:   1 SREMSKOG ODREDA: 2-90,1-89,  AERODROMSKA: 68A-80,84-88I,98,1-1A,5-13,23A,",
+    "NOVOSADSKA: 75Ž,  MALA (DEO): 2-10,1,  BRODSKA: 2-18,",
+    "BULEVAR MIHAJLA PUPINA: 10-14,  NARODNIH HEROJA: 2-6,",
+];
+
+const REAL_TIMES: &[&str] = &[
+    "08:30-14:00",
+    "od 8 do 10",
+    "8h-10h",
+    "22:00-06:00, 13:00-15:00",
+];
+
+/// Parses the addresses and goes over the result the way the outputs do.
+fn exercise_addresses(input: &str) {
+    if let Ok(addresses) = Addresses::parse(input) {
+        let _ = addresses.to_string();
+        let _ = addresses.sorted();
+        let _ = addresses.street_count();
+        for record in &addresses {
+            let _ = record.validate();
+            let _ = record.canonical();
+            let _ = record.contains(10, None);
+        }
+    }
+    let (addresses, _) = Addresses::parse_lenient(input);
+    let _ = addresses.to_string();
+}
+
+/// A real row with a random piece cut out and random text put in its
+/// place.
+fn mutated_row() -> impl Strategy<Value = String> {
+    (
+        proptest::sample::select(REAL_ROWS),
+        any::<proptest::sample::Index>(),
+        0..16usize,
+        "[0-9A-ZČĆŠŽĐ ,:\\-()/.]{0,8}",
+    )
+        .prop_map(|(row, at, cut, insert)| {
+            let chars = row.chars().collect::<Vec<_>>();
+            let at = at.index(chars.len());
+            let end = (at + cut).min(chars.len());
+            chars[..at]
+                .iter()
+                .chain(insert.chars().collect::<Vec<_>>().iter())
+                .chain(chars[end..].iter())
+                .collect()
+        })
+}
+
+fn mutated_time() -> impl Strategy<Value = String> {
+    (
+        proptest::sample::select(REAL_TIMES),
+        any::<proptest::sample::Index>(),
+        "[0-9:\\-hč, odD]{0,6}",
+    )
+        .prop_map(|(time, at, insert)| {
+            let chars = time.chars().collect::<Vec<_>>();
+            let at = at.index(chars.len());
+            chars[..at]
+                .iter()
+                .copied()
+                .chain(insert.chars())
+                .chain(chars[at..].iter().copied())
+                .collect()
+        })
+}
+
+proptest! {
+    #[test]
+    fn addresses_never_panic_on_any_text(input in "\\PC{0,200}") {
+        exercise_addresses(&input);
+    }
+
+    #[test]
+    fn addresses_never_panic_on_mutated_rows(input in mutated_row()) {
+        exercise_addresses(&input);
+    }
+
+    #[test]
+    fn addresses_never_panic_on_address_like_text(
+        input in "([A-ZČŠ ]{1,12}:( ?[0-9]{1,6}[A-Z]?(-[0-9]{1,6}[A-Z]?)?,){0,6} {0,3}){0,5}"
+    ) {
+        exercise_addresses(&input);
+    }
+
+    #[test]
+    fn time_range_never_panics(input in "\\PC{0,40}") {
+        let _ = TimeRange::parse(&input);
+        let _ = TimeRange::parse_list(&input);
+    }
+
+    #[test]
+    fn time_range_never_panics_on_mutated_times(input in mutated_time()) {
+        let _ = TimeRange::parse(&input);
+        let _ = TimeRange::parse_list(&input);
+    }
+
+    #[test]
+    fn time_range_never_panics_on_large_numbers(
+        hh in any::<u64>(), mm in any::<u64>(), to in any::<u64>()
+    ) {
+        let _ = TimeRange::parse(&format!("{hh}:{mm}-{to}:{mm}"));
+        let _ = TimeRange::parse(&format!("od {hh} do {to}"));
+    }
+
+    #[test]
+    fn transliteration_never_panics(input in "\\PC{0,200}") {
+        let mapper = Mapper::new();
+        let _ = mapper.transoform(&input);
+    }
+}
+
+proptest! {
+    // each case runs in a child process which is killed after the timeout
+    #![proptest_config(ProptestConfig {
+        cases: 32,
+        timeout: 5_000,
+        ..ProptestConfig::default()
+    })]
+
+    #[test]
+    fn parsers_terminate_on_bounded_input(input in "[0-9A-ZČ ,:\\-]{0,400}") {
+        exercise_addresses(&input);
+        let _ = TimeRange::parse_list(&input);
+    }
+}
@@ -0,0 +1,351 @@
+use beobot::addresses::{AddressRecord, Broj, BrojNumber, BrojRange};
+use beobot::cli::Cli;
+use beobot::job::ScrapeJob;
+use beobot::scrape::{self, PageParser, PageStats};
+use beobot::source::StaticSource;
+use clap::Parser;
+use futures::StreamExt;
+
+mod common;
+
+use common::CapturedLogs;
+
+static BEOGRAD_DAY_0: &str = include_str!("data/beograd_day0.html");
+
+static MALFORMED_ROWS: &str = include_str!("data/malformed_rows.html");
+
+#[cfg(feature = "serde")]
+static ONE_MALFORMED_ROW: &str = include_str!("data/one_malformed_row.html");
+
+static WRAPPED_TABLE: &str = include_str!("data/wrapped_table.html");
+
+static WITH_STATION: &str = include_str!("data/with_station.html");
+
+#[test]
+fn test_parse_saved_day_page() {
+    let page_parser = PageParser::new().expect("build the page parser");
+    let outages = page_parser
+        .parse_page(BEOGRAD_DAY_0)
+        .expect("parse the saved day page")
+        .outages;
+
+    assert_eq!(outages.len(), 2);
+    assert_eq!(outages[0].municipality, "ZEMUN");
+    assert_eq!(outages[0].time, "08:30-14:00");
+    assert_eq!(outages[0].time_ranges.len(), 1);
+    assert_eq!(outages[0].station, None);
+
+    let addresses = outages[0].addresses().expect("parse the streets column");
+    let batajnicki_drum = addresses.into_iter().find(|record| {
+        *record
+            == AddressRecord::new(
+                "BATAJNIČKI DRUM",
+                vec![
+                    Broj::Bez,
+                    Broj::from(BrojRange::from((261, 265))),
+                    Broj::from(BrojNumber::from(269)),
+                    Broj::from(BrojRange::from((283, 293))),
+                    Broj::from(BrojNumber::from(299)),
+                    Broj::from(BrojRange::from(((303, None), (303, Some("A"))))),
+                ],
+            )
+    });
+    assert!(batajnicki_drum.is_some());
+}
+
+#[test]
+fn test_page_without_data_table() {
+    let page_parser = PageParser::new().expect("build the page parser");
+    let res = page_parser.parse_page("<html><body><p>Not found</p></body></html>");
+    assert!(res.is_err());
+}
+
+#[test]
+fn test_page_stats_count_malformed_rows() {
+    let page_parser = PageParser::new().expect("build the page parser");
+    let page = page_parser
+        .parse_page(MALFORMED_ROWS)
+        .expect("parse the page with broken rows");
+
+    assert_eq!(
+        page.stats,
+        PageStats {
+            total: 4,
+            parsed: 2,
+            malformed: 2,
+        }
+    );
+    assert_eq!(page.outages.len(), 2);
+    assert_eq!(page.stats.to_string(), "2/4 rows parsed, 2 malformed");
+}
+
+#[test]
+fn test_saved_page_with_bom_and_crlf() {
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data/bom_crlf.html");
+    let body = scrape::read_page_file(&path).expect("read the saved page");
+    assert!(!body.starts_with('\u{feff}'));
+    assert!(!body.contains('\r'));
+
+    let page_parser = PageParser::new().expect("build the page parser");
+    let page = page_parser.parse_page(&body).expect("parse the saved page");
+    assert_eq!(page.stats.parsed, 2);
+    assert_eq!(page.outages[0].municipality, "ZEMUN");
+    for outage in page.outages.iter() {
+        let addresses = outage.addresses().expect("parse the addresses");
+        for record in addresses.into_iter() {
+            assert!(!record.street().contains(['\r', '\u{feff}']));
+        }
+    }
+    assert_eq!(
+        page.outages[1].streets,
+        "BULEVAR MIHAJLA PUPINA: 10-14, NARODNIH HEROJA: 2-6,"
+    );
+}
+
+#[test]
+fn test_batch_of_saved_pages() {
+    let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data/batch");
+    let files = scrape::page_files(&[dir]).expect("list the saved pages");
+    let names = files
+        .iter()
+        .filter_map(|file| file.file_name()?.to_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["day0.html", "day1.html", "day2.html"]);
+
+    let page_parser = PageParser::new().expect("build the page parser");
+    let mut outages = Vec::new();
+    // the last page has no outages table, it is skipped
+    let failed = scrape::scrape_files_with(&files, |day, body| {
+        let page = page_parser.parse_page(body)?;
+        outages.extend(page.outages.into_iter().map(|outage| (day, outage)));
+        Ok(page.stats)
+    });
+
+    assert_eq!(failed, 1);
+    assert_eq!(outages.len(), 4);
+    assert!(outages.iter().any(|(day, _)| *day == 0));
+    assert!(outages
+        .iter()
+        .any(|(day, outage)| *day == 1 && outage.station.is_some()));
+}
+
+#[test]
+fn test_strict_fails_on_malformed_row() {
+    let parse = |args: &[&str]| {
+        let cli = Cli::try_parse_from(args).expect("parse the arguments");
+        PageParser::new()
+            .expect("build the page parser")
+            .strict(cli.strict)
+            .parse_page(MALFORMED_ROWS)
+    };
+
+    let error = parse(&["beobot", "--strict"]).expect_err("the broken row fails the page");
+    let message = error.to_string();
+    assert!(message.contains("malformed row #1"));
+    assert!(message.contains("Вождовац | 09:00-11:00"));
+
+    let page = parse(&["beobot"]).expect("the broken rows are skipped");
+    assert_eq!(page.stats.malformed, 2);
+}
+
+#[test]
+fn test_data_table_found_by_fallback_selector() {
+    let page_parser = PageParser::new().expect("build the page parser");
+    let page = page_parser
+        .parse_page(WRAPPED_TABLE)
+        .expect("find the data table in the wrapped layout");
+
+    assert_eq!(page.stats.parsed, 1);
+    assert_eq!(page.outages[0].municipality, "ZEMUN");
+}
+
+#[test]
+fn test_station_column() {
+    let page_parser = PageParser::new().expect("build the page parser");
+    let page = page_parser
+        .parse_page(WITH_STATION)
+        .expect("parse the page with the station column");
+
+    assert_eq!(page.stats.parsed, 2);
+    assert_eq!(
+        page.outages[0].station.as_deref(),
+        Some("TS 10/0,4 KV \"BATAJNICA 5\"")
+    );
+    assert_eq!(page.outages[1].station, None);
+}
+
+#[test]
+fn test_row_warnings_carry_page_span() {
+    let logs = CapturedLogs::default();
+
+    tracing::subscriber::with_default(logs.subscriber(), || {
+        let _span = scrape::page_span("https://example.com/Dan_1_Iskljucenja.htm", 1).entered();
+        let page_parser = PageParser::new().expect("build the page parser");
+        page_parser
+            .parse_page(MALFORMED_ROWS)
+            .expect("parse the page with broken rows");
+    });
+
+    let warning = logs
+        .contents()
+        .lines()
+        .find(|line| line.contains("malformed row #1"))
+        .expect("the malformed row is reported")
+        .to_owned();
+    assert!(warning.contains("page{url=https://example.com/Dan_1_Iskljucenja.htm day=1}"));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_jsonl_line_per_outage() {
+    let page_parser = PageParser::new().expect("build the page parser");
+    let mut buffer = Vec::new();
+    let stats = page_parser
+        .for_each_outage(BEOGRAD_DAY_0, |outage| {
+            beobot::output::write_jsonl(&mut buffer, &outage)
+        })
+        .expect("parse the saved day page");
+
+    let text = String::from_utf8(buffer).expect("utf-8 output");
+    assert!(text.ends_with('\n'));
+    let lines = text.lines().collect::<Vec<_>>();
+    assert_eq!(stats.parsed, 2);
+    assert_eq!(lines.len(), stats.parsed);
+    for line in lines {
+        let value: serde_json::Value = serde_json::from_str(line).expect("a JSON object per line");
+        assert!(value.is_object());
+        assert_eq!(
+            value["schema_version"],
+            beobot::output::OUTPUT_SCHEMA_VERSION
+        );
+        assert!(value["municipality"].is_string());
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_json_by_street() {
+    let page_parser = PageParser::new().expect("build the page parser");
+    let outages = [BEOGRAD_DAY_0, WITH_STATION]
+        .into_iter()
+        .flat_map(|body| {
+            page_parser
+                .parse_page(body)
+                .expect("parse the page")
+                .outages
+        })
+        .collect::<Vec<_>>();
+    let mut buffer = Vec::new();
+    beobot::output::write_json_by_street(&mut buffer, &outages).expect("write the document");
+
+    let text = String::from_utf8(buffer).expect("utf-8 output");
+    let value: serde_json::Value = serde_json::from_str(&text).expect("a JSON document");
+    let streets = value.as_object().expect("an object keyed by street");
+    let keys = streets.keys().collect::<Vec<_>>();
+    assert!(keys.windows(2).all(|pair| pair[0] < pair[1]));
+    // the keys are written sorted, not only read back sorted
+    let positions = keys
+        .iter()
+        .map(|key| {
+            text.find(&format!("\"{key}\":"))
+                .expect("the key is in the text")
+        })
+        .collect::<Vec<_>>();
+    assert!(positions.windows(2).all(|pair| pair[0] < pair[1]));
+
+    // the street is on both pages, its numbers are concatenated
+    let batajnicki_drum = &streets["BATAJNIČKI DRUM"];
+    assert_eq!(batajnicki_drum["time"], "08:30-14:00");
+    assert_eq!(
+        batajnicki_drum["numbers"],
+        serde_json::json!([
+            "BB", "261-265", "269", "283-293", "299", "303-303A", "BB", "261-265", "269"
+        ])
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_json_document_lists_warnings() {
+    use beobot::output::{self, RowWarning};
+
+    let page_parser = PageParser::new().expect("build the page parser");
+    let page = page_parser
+        .parse_page(ONE_MALFORMED_ROW)
+        .expect("parse the page with a broken row");
+    let warnings = page
+        .malformed_rows
+        .iter()
+        .map(|row| RowWarning::new(2, row))
+        .collect::<Vec<_>>();
+    let mut buffer = Vec::new();
+    output::write_json(&mut buffer, &page.outages, &warnings).expect("write the document");
+
+    let value: serde_json::Value = serde_json::from_slice(&buffer).expect("a JSON document");
+    assert_eq!(value["schema_version"], output::OUTPUT_SCHEMA_VERSION);
+    assert_eq!(value["outages"].as_array().map(Vec::len), Some(2));
+    let warnings = value["warnings"].as_array().expect("the warnings array");
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0]["day"], 2);
+    assert_eq!(warnings[0]["row"], 1);
+    assert_eq!(warnings[0]["text"], "Вождовац | 09:00-11:00");
+}
+
+#[tokio::test]
+async fn test_stream_outages() {
+    let day_pages = ScrapeJob::builder()
+        .base_url("http://localhost")
+        .day_pages()
+        .expect("the day pages");
+    let source = StaticSource::new()
+        .with_page(&day_pages[0].url, BEOGRAD_DAY_0)
+        .with_page(&day_pages[1].url, WITH_STATION);
+    let page_parser = PageParser::new().expect("build the page parser");
+
+    let outages = scrape::stream_outages(&source, &page_parser, &day_pages)
+        .collect::<Vec<_>>()
+        .await
+        .into_iter()
+        .collect::<anyhow::Result<Vec<_>>>()
+        .expect("all the pages are parsed");
+
+    assert_eq!(outages.len(), 4);
+    assert_eq!(
+        outages
+            .iter()
+            .filter(|outage| outage.municipality == "ZEMUN")
+            .count(),
+        2
+    );
+    assert!(outages.iter().any(|outage| outage.station.is_some()));
+
+    assert_eq!(source.requested().len(), day_pages.len());
+}
+
+#[test]
+fn test_include_file_keeps_listed_streets() {
+    let include_file = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/data/include_streets.txt"
+    );
+    let cli = Cli::try_parse_from(["beobot", "--include-file", include_file])
+        .expect("parse the include file option");
+    let filter = cli.outage_filter().expect("read the include file");
+
+    let page_parser = PageParser::new().expect("build the page parser");
+    let outages = page_parser
+        .parse_page(BEOGRAD_DAY_0)
+        .expect("parse the saved day page")
+        .outages;
+    let kept = outages
+        .iter()
+        .filter(|outage| filter.matches(outage))
+        .collect::<Vec<_>>();
+
+    assert_eq!(kept.len(), 1);
+    assert_eq!(kept[0].municipality, "NOVI BEOGRAD");
+
+    let cli = Cli::try_parse_from(["beobot", "--include-file", "no/such/file.txt"])
+        .expect("parse the include file option");
+    assert!(cli.outage_filter().is_err());
+}